@@ -0,0 +1,426 @@
+use crate::{
+    date_utils::{convert_str_due, convert_str_valid_date},
+    filter::{parse_filter_date, Filter},
+    history::{self, Snapshot},
+    io,
+    model::{extract_tags, MyDate, Todo, MAXPRIORITY},
+    report::{Report, ReportPeriod},
+    service, storage,
+    view::View,
+};
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+// Command line arguments, the interactive screen is launched when no subcommand is given
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Name of the list to work on
+    #[arg(short, long, global = true, default_value = storage::DEFAULT_LIST)]
+    pub list: String,
+    /// Name of a view defined in the configuration as VIEW_<NAME>="<filter> | <sort>"
+    #[arg(long, global = true)]
+    pub view: Option<String>,
+    /// Start the interactive screen in the full-screen mode, with a detail pane (FULL_SCREEN in the configuration)
+    #[arg(short = 'F', long)]
+    pub full_screen: bool,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Add a new TODO
+    Add {
+        /// Title, inline #tags are extracted
+        title: String,
+        /// Due date [dd-mm(-YYYY) or mm-dd(-YYYY) depending on DATE_FORMAT, or YYYY-mm-dd], or "5 nov", tomorrow, friday, "in 3 days", +2w, eom, a recurrence like "every monday"..., optionally followed by a time [14:00, 9am]
+        #[arg(short, long)]
+        due: Option<String>,
+        /// Start date, the TODO is hidden from the list until then
+        #[arg(short, long)]
+        start: Option<String>,
+        /// Priority level
+        #[arg(short, long, default_value_t = 0, value_parser = clap::value_parser!(u32).range(..=MAXPRIORITY as i64))]
+        priority: u32,
+    },
+    /// List all TODOs using the default sorting method, or the filter and sorting method of the view
+    #[command(alias = "ls")]
+    List {
+        /// Only list TODOs with this tag
+        #[arg(short, long)]
+        tag: Option<String>,
+        /// Only list TODOs matching a filter expression, e.g. 'due<2026-11-01 priority>=2 !done tag:work'
+        #[arg(short, long)]
+        filter: Option<String>,
+        /// Also list the TODOs with a future start date
+        #[arg(short, long)]
+        all: bool,
+    },
+    /// Mark a TODO as completed
+    Done { id_prefix: String },
+    /// Delete a TODO
+    Rm { id_prefix: String },
+    /// Edit the fields of a TODO
+    Edit {
+        id_prefix: String,
+        /// New title, inline #tags replace the existing tags
+        #[arg(short, long)]
+        title: Option<String>,
+        #[arg(short, long, conflicts_with = "clear_due")]
+        due: Option<String>,
+        /// Remove the due date
+        #[arg(long)]
+        clear_due: bool,
+        #[arg(short, long, conflicts_with = "clear_start")]
+        start: Option<String>,
+        /// Remove the start date
+        #[arg(long)]
+        clear_start: bool,
+        #[arg(short, long, value_parser = clap::value_parser!(u32).range(..=MAXPRIORITY as i64))]
+        priority: Option<u32>,
+        /// New notes (\n starts a new line), an empty string clears them
+        #[arg(short, long)]
+        notes: Option<String>,
+    },
+    /// Show every field of a TODO, with its checklist and notes
+    Show { id_prefix: String },
+    /// Merge the TODOs from the markdown file back into the database
+    Import,
+    /// Move a TODO to another list
+    Mv {
+        id_prefix: String,
+        target_list: String,
+    },
+    /// Show all the existing lists
+    Lists,
+    /// Undo the last change, made on the command line or in the interactive screen
+    Undo,
+    /// Redo the last undone change
+    Redo,
+    /// Summarize the completed TODOs of the list and its archive
+    Report {
+        /// Only count completions and progress changes from this date, in any due date format
+        #[arg(short, long)]
+        since: Option<String>,
+        /// Group by day or by week
+        #[arg(short, long, value_enum, default_value_t = ReportPeriod::Day)]
+        by: ReportPeriod,
+        /// Export the report to a markdown file instead of printing it
+        #[arg(short, long)]
+        markdown: Option<PathBuf>,
+    },
+}
+
+// Runs a non-interactive subcommand against the database
+// The view only applies to the list subcommand
+pub fn run_command(
+    db: &mut storage::DatabaseModel,
+    list: &str,
+    view: Option<&View>,
+    command: Command,
+) -> Result<()> {
+    // Undoing is not recorded itself, so that it can be redone
+    match command {
+        Command::Undo => return command_undo(db, list),
+        Command::Redo => return command_redo(db, list),
+        _ => (),
+    }
+    // The changes made by the command are recorded in the history to be undone
+    // The commands which only read the TODOs do not copy the list
+    let snapshot = match &command {
+        Command::Mv { target_list, .. } => {
+            let mut snapshot = Snapshot::take(db, list)?;
+            snapshot.add_list(target_list)?;
+            Some(snapshot)
+        }
+        Command::Add { .. }
+        | Command::Done { .. }
+        | Command::Rm { .. }
+        | Command::Edit { .. }
+        | Command::Import => Some(Snapshot::take(db, list)?),
+        Command::List { .. }
+        | Command::Show { .. }
+        | Command::Lists
+        | Command::Report { .. }
+        | Command::Undo
+        | Command::Redo => None,
+    };
+    let result = match command {
+        Command::Add {
+            title,
+            due,
+            start,
+            priority,
+        } => command_add(db, &title, due.as_deref(), start.as_deref(), priority),
+        Command::List { tag, filter, all } => {
+            command_list(db, view, tag.as_deref(), filter.as_deref(), all)
+        }
+        Command::Done { id_prefix } => command_done(db, &id_prefix),
+        Command::Rm { id_prefix } => command_rm(db, &id_prefix),
+        Command::Edit {
+            id_prefix,
+            title,
+            due,
+            clear_due,
+            start,
+            clear_start,
+            priority,
+            notes,
+        } => command_edit(
+            db,
+            &id_prefix,
+            EditFields {
+                title: title.as_deref(),
+                due: due.as_deref(),
+                clear_due,
+                start: start.as_deref(),
+                clear_start,
+                priority,
+                notes: notes.as_deref(),
+            },
+        ),
+        Command::Show { id_prefix } => command_show(db, &id_prefix),
+        Command::Import => command_import(db, list),
+        Command::Mv {
+            id_prefix,
+            target_list,
+        } => command_mv(db, list, &id_prefix, &target_list),
+        Command::Lists => command_lists(),
+        Command::Report {
+            since,
+            by,
+            markdown,
+        } => command_report(db, list, since.as_deref(), by, markdown),
+        Command::Undo | Command::Redo => Ok(()),
+    };
+    if let Some(snapshot) = snapshot {
+        history::record(snapshot.diff(db)?)?;
+    }
+    result
+}
+
+// Checks the title and due date given on the command line, returns the normalized due date
+fn validate_fields(title: Option<&str>, due: Option<&str>) -> Result<Option<String>> {
+    if title.is_some_and(|t| t.trim().is_empty()) {
+        bail!("The title cannot be empty!");
+    }
+    match due {
+        Some(due_str) => {
+            let due_lower = due_str.to_lowercase();
+            if convert_str_due(&due_lower).is_err() {
+                bail!("Invalid due date '{}'!", due_str);
+            }
+            Ok(Some(due_lower))
+        }
+        None => Ok(None),
+    }
+}
+
+// Reads the start date given on the command line
+fn validate_start(start: Option<&str>) -> Result<Option<MyDate>> {
+    start
+        .map(|start_str| {
+            convert_str_valid_date(start_str)
+                .map(MyDate)
+                .with_context(|| format!("Invalid start date '{}'!", start_str))
+        })
+        .transpose()
+}
+
+fn command_add(
+    db: &mut storage::DatabaseModel,
+    title: &str,
+    due: Option<&str>,
+    start: Option<&str>,
+    priority: u32,
+) -> Result<()> {
+    let (title, tags) = extract_tags(title);
+    let due_date = validate_fields(Some(&title), due)?;
+    let scheduled = validate_start(start)?;
+    let mut todo = Todo::new(&title, priority, due_date.as_deref());
+    todo.set_tags(tags);
+    todo.set_scheduled_date(scheduled);
+    storage::insert_todo(db, &todo)?;
+    io::confirm_message(&format!("Added {}", todo.get_id()))
+}
+
+// The filter given on the command line is combined with the filter of the view
+// TODOs with a future start date are only listed with --all
+fn command_list(
+    db: &mut storage::DatabaseModel,
+    view: Option<&View>,
+    tag: Option<&str>,
+    filter: Option<&str>,
+    all: bool,
+) -> Result<()> {
+    let filter = filter.map(Filter::parse).transpose()?;
+    let view_filter = view.and_then(|view| view.get_filter().as_ref());
+    let filters: Vec<&Filter> = filter.iter().chain(view_filter).collect();
+    let todos_db = match filters.first() {
+        Some(first) => storage::get_filtered_todos(db, first)?,
+        None => storage::get_todos(db)?,
+    };
+    let mut todos = service::get_todo_tuple(todos_db);
+    todos.retain(|todo| filters.iter().all(|filter| filter.matches(todo)));
+    if let Some(tag) = tag {
+        let tag = tag.trim_start_matches('#').to_lowercase();
+        todos.retain(|todo| todo.has_tag(&tag));
+    }
+    if !all {
+        todos.retain(|todo| !todo.is_scheduled_later());
+    }
+    let sorting_method = match view.and_then(|view| view.get_sort().clone()) {
+        Some(sorting_method) => sorting_method,
+        None => service::get_default_sort()?,
+    };
+    service::sort_todos(&mut todos, &sorting_method);
+    io::write_todo_list(&todos)
+}
+
+fn command_done(db: &mut storage::DatabaseModel, id_prefix: &str) -> Result<()> {
+    let mut todo = storage::get_todo_by_id_prefix(db, id_prefix)?;
+    if todo.is_complete() {
+        return io::confirm_message(&format!("'{}' is already completed", todo.get_title()));
+    }
+    service::toggle_todo(db, &mut todo)?;
+    io::confirm_message(&format!("Completed '{}'", todo.get_title()))
+}
+
+fn command_rm(db: &mut storage::DatabaseModel, id_prefix: &str) -> Result<()> {
+    let todo = storage::get_todo_by_id_prefix(db, id_prefix)?;
+    storage::delete_todo(db, &todo)?;
+    io::confirm_message(&format!("Deleted '{}'", todo.get_title()))
+}
+
+// Fields given to the edit subcommand, the fields left to None are not changed
+struct EditFields<'a> {
+    title: Option<&'a str>,
+    due: Option<&'a str>,
+    clear_due: bool,
+    start: Option<&'a str>,
+    clear_start: bool,
+    priority: Option<u32>,
+    notes: Option<&'a str>,
+}
+
+fn command_edit(
+    db: &mut storage::DatabaseModel,
+    id_prefix: &str,
+    fields: EditFields,
+) -> Result<()> {
+    let EditFields {
+        title,
+        due,
+        clear_due,
+        start,
+        clear_start,
+        priority,
+        notes,
+    } = fields;
+    let title_tags = title.map(extract_tags);
+    let due_date = validate_fields(title_tags.as_ref().map(|(t, _)| t.as_str()), due)?;
+    let scheduled = validate_start(start)?;
+    let mut todo = storage::get_todo_by_id_prefix(db, id_prefix)?;
+    if let Some((title, tags)) = title_tags {
+        todo.set_title(&title);
+        if !tags.is_empty() {
+            todo.set_tags(tags);
+        }
+    }
+    if due_date.is_some() || clear_due {
+        todo.set_due_date(due_date.as_deref());
+    }
+    if scheduled.is_some() || clear_start {
+        todo.set_scheduled_date(scheduled);
+    }
+    if let Some(priority) = priority {
+        todo.set_priority(priority);
+    }
+    if let Some(notes) = notes {
+        todo.set_notes(notes.replace("\\n", "\n").trim_end());
+    }
+    storage::update_todo(db, &todo)?;
+    io::confirm_message(&format!("Edited '{}'", todo.get_title()))
+}
+
+fn command_show(db: &mut storage::DatabaseModel, id_prefix: &str) -> Result<()> {
+    let todo = storage::get_todo_by_id_prefix(db, id_prefix)?;
+    io::write_todo_details(&todo)
+}
+
+fn command_import(db: &mut storage::DatabaseModel, list: &str) -> Result<()> {
+    let (count, errors) = service::import_todos(db, list)?;
+    io::write_import_report(count, &errors)
+}
+
+fn command_mv(
+    db: &mut storage::DatabaseModel,
+    list: &str,
+    id_prefix: &str,
+    target_list: &str,
+) -> Result<()> {
+    storage::validate_list_name(target_list)?;
+    if list == target_list {
+        bail!("The TODO is already in the list '{}'!", list);
+    }
+    let todo = storage::get_todo_by_id_prefix(db, id_prefix)?;
+    service::move_todo(db, &todo, list, target_list)?;
+    io::confirm_message(&format!(
+        "Moved '{}' to the list '{}'",
+        todo.get_title(),
+        target_list
+    ))
+}
+
+fn command_lists() -> Result<()> {
+    for list in storage::get_lists()? {
+        io::confirm_message(&list)?;
+    }
+    Ok(())
+}
+
+fn command_undo(db: &mut storage::DatabaseModel, list: &str) -> Result<()> {
+    match history::undo(db, list)? {
+        Some(description) => io::confirm_message(&format!("Undone: {}", description)),
+        None => io::confirm_message("Nothing to undo"),
+    }
+}
+
+fn command_redo(db: &mut storage::DatabaseModel, list: &str) -> Result<()> {
+    match history::redo(db, list)? {
+        Some(description) => io::confirm_message(&format!("Redone: {}", description)),
+        None => io::confirm_message("Nothing to redo"),
+    }
+}
+
+fn command_report(
+    db: &mut storage::DatabaseModel,
+    list: &str,
+    since: Option<&str>,
+    period: ReportPeriod,
+    markdown: Option<PathBuf>,
+) -> Result<()> {
+    let since = since
+        .map(|since| parse_filter_date(&since.to_lowercase()))
+        .transpose()?;
+    let mut todos = service::get_todo_tuple(storage::get_todos(db)?);
+    todos.extend(service::get_todo_tuple(storage::get_todos(
+        &mut storage::connect_archive(list)?,
+    )?));
+    let report = Report::build(&todos, since, period);
+    match markdown {
+        Some(path) => {
+            std::fs::write(&path, report.to_markdown())
+                .with_context(|| format!("Error writing {}!", path.display()))?;
+            io::confirm_message(&format!("Report exported to {}", path.display()))
+        }
+        None => {
+            for line in report.to_table() {
+                io::confirm_message(&line)?;
+            }
+            Ok(())
+        }
+    }
+}
@@ -1,12 +1,26 @@
+use crate::model::Recurrence;
 use anyhow::Result;
 use chrono::prelude::*;
-use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use chrono::{Datelike, Duration, Months, NaiveDate, Weekday};
 use once_cell::sync::Lazy;
 use regex::Regex;
+use std::env;
 
+// Format of the dates in the DB, independent of the DATE_FORMAT setting
 pub const FORMAT_DATE: &str = "%d-%m-%Y";
-pub const FORMAT_DATETIME: &str = "%d-%m-%Y %h-%m-%s";
-pub const ALLOWEDNONDATE: [&str; 4] = ["today", "tomorrow", "next week", "next month"];
+const FORMAT_ISO_DATE: &str = "%Y-%m-%d";
+pub const FORMAT_TIME: &str = "%H:%M";
+pub const FORMAT_DATETIME: &str = "%Y-%m-%dT%H:%M:%S%.f";
+pub const ALLOWEDNONDATE: [&str; 8] = [
+    "today",
+    "tomorrow",
+    "next week",
+    "next month",
+    "end of week",
+    "eow",
+    "end of month",
+    "eom",
+];
 pub const ALLOWEDWEEKDAY: [&str; 7] = [
     "monday",
     "tuesday",
@@ -16,84 +30,623 @@ pub const ALLOWEDWEEKDAY: [&str; 7] = [
     "saturday",
     "sunday",
 ];
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
 
-// Counts the number of occurences of a given character
-fn count_char(s: &str, ch: char) -> usize {
-    s.chars().filter(|c| *c == ch).count()
+// Order of the day and month in the dates entered and displayed, set with DATE_FORMAT
+#[derive(Clone, Copy, PartialEq)]
+pub enum DateFormat {
+    DayMonth,
+    MonthDay,
+    Iso,
 }
-
-// Converts a string into the appriopriate date element -- calls the correct function depending on the type of string
-pub fn convert_str_valid_date(due_str: &str) -> Result<NaiveDate> {
-    // The first match checks whether the user specified the year (otherwise we fill it with the current year)
-    let date_ret = match count_char(due_str, '-') {
-        1 => {
-            NaiveDate::parse_from_str(&format!("{}-{}", due_str, Local::now().year()), FORMAT_DATE)?
+impl DateFormat {
+    // Reads DATE_FORMAT ("dd-mm", "mm-dd" or "iso"), dd-mm if it is not set or not valid
+    pub fn get() -> DateFormat {
+        match env::var("DATE_FORMAT")
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "mm-dd" => DateFormat::MonthDay,
+            "iso" | "yyyy-mm-dd" => DateFormat::Iso,
+            _ => DateFormat::DayMonth,
         }
-        0 => {
-            // Deals with special inputs
-            if ALLOWEDNONDATE.contains(&due_str) {
-                get_language_date(due_str)?
-            } else if ALLOWEDWEEKDAY.contains(&due_str) {
-                get_next_weekday(due_str)?
-            } else {
-                anyhow::bail!("Wrong date format!");
-            }
+    }
+    pub fn get_pattern(&self) -> &'static str {
+        match self {
+            DateFormat::DayMonth => FORMAT_DATE,
+            DateFormat::MonthDay => "%m-%d-%Y",
+            DateFormat::Iso => FORMAT_ISO_DATE,
         }
-        _ => NaiveDate::parse_from_str(due_str, FORMAT_DATE)?,
-    };
-    Ok(date_ret)
+    }
+    // Syntax of the numeric dates, shown in the prompts
+    pub fn get_hint(&self) -> &'static str {
+        match self {
+            DateFormat::DayMonth => "dd-mm(-YYYY)",
+            DateFormat::MonthDay => "mm-dd(-YYYY)",
+            DateFormat::Iso => "(YYYY-)mm-dd",
+        }
+    }
 }
 
-// Validates the input from user
-pub fn validate_regex(s: &String) -> Result<(), &'static str> {
-    // This makes sure the regex only initializes once
-    static REWORD: Lazy<Regex> = Lazy::new(|| {
-        Regex::new(r"^(0[1-9]|[12][0-9]|3[01])-(0[1-9]|1[012])(-(19|20)\d\d)?$").unwrap()
+// Formats a date with the configured DATE_FORMAT
+pub fn format_date(date: &NaiveDate) -> String {
+    date.format(DateFormat::get().get_pattern()).to_string()
+}
+
+// Formats a date and time with the configured DATE_FORMAT
+pub fn format_datetime(datetime: &NaiveDateTime) -> String {
+    datetime
+        .format(&format!("{} %H:%M", DateFormat::get().get_pattern()))
+        .to_string()
+}
+
+// Formats a due date for display, relative to today when RELATIVE_DATES is enabled
+pub fn format_due_date(date: &NaiveDate) -> String {
+    let relative = env::var("RELATIVE_DATES")
+        .unwrap_or("false".to_string())
+        .parse::<bool>()
+        .unwrap_or(false);
+    if !relative {
+        return format_date(date);
+    }
+    match (*date - Local::now().date_naive()).num_days() {
+        0 => "today".to_string(),
+        1 => "tomorrow".to_string(),
+        -1 => "1 day overdue".to_string(),
+        n if n > 1 => format!("in {} days", n),
+        n => format!("{} days overdue", -n),
+    }
+}
+
+// Formats a date of the markdown file, always ISO so that the file does not depend on DATE_FORMAT
+pub fn format_md_date(date: &NaiveDate) -> String {
+    date.format(FORMAT_ISO_DATE).to_string()
+}
+
+// Reads a date of the markdown file, ISO or dd-mm-YYYY as written by older versions
+pub fn parse_md_date(date_str: &str) -> Result<NaiveDate> {
+    Ok(NaiveDate::parse_from_str(date_str, FORMAT_ISO_DATE)
+        .or_else(|_| NaiveDate::parse_from_str(date_str, FORMAT_DATE))?)
+}
+
+// Converts a string into the appriopriate date element -- tries each accepted syntax in turn:
+// special phrases, (next) weekdays, offsets ("in 3 days", "+2w"), dd-mm(-YYYY) or mm-dd(-YYYY)
+// depending on DATE_FORMAT, YYYY-mm-dd,
+// month names ("5 nov", "november 5th") and recurrence rules (first occurrence)
+pub fn convert_str_valid_date(due_str: &str) -> Result<NaiveDate> {
+    let due_lower = due_str.trim().to_lowercase();
+    let due_str = due_lower.as_str();
+    if ALLOWEDNONDATE.contains(&due_str) {
+        return get_language_date(due_str);
+    }
+    let weekday_str = due_str.strip_prefix("next ").unwrap_or(due_str);
+    if let Ok(day) = weekday_str.parse::<Weekday>() {
+        return Ok(get_next_weekday(day));
+    }
+    let date = get_offset_date(due_str)
+        .or_else(|| get_numeric_date(due_str, DateFormat::get()))
+        .or_else(|| get_month_name_date(due_str));
+    if let Some(date) = date {
+        return Ok(date);
+    }
+    match convert_str_recurrence(due_str) {
+        Ok(recurrence) => Ok(get_first_occurrence(&recurrence)),
+        Err(_) => anyhow::bail!("Wrong date format!"),
+    }
+}
+
+// Splits an optional time of day from the end of a due date: "tomorrow 14:00", "25-10 9am",
+// "friday at 5:30pm". Returns the remaining date part, empty for a time alone
+pub fn split_due_time(due_str: &str) -> Result<(String, Option<NaiveTime>)> {
+    static RETIME: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"^(.*?)\s*(?:\bat\s+)?\b(\d{1,2})(?::(\d{2}))?\s*(am|pm)?$").unwrap()
     });
-    // Allow for regex matches, empty strings or various special inputs
-    match REWORD.is_match(s)
-        || s.is_empty()
-        || ALLOWEDNONDATE.contains(&s.as_str())
-        || ALLOWEDWEEKDAY.contains(&s.as_str())
-    {
-        true => Ok(()),
-        false => Err("Invalid date!"),
+    let due_lower = due_str.trim().to_lowercase();
+    let caps = match RETIME.captures(&due_lower) {
+        // A bare number is a day ("nov 5"), times need minutes or am/pm
+        Some(caps) if caps.get(3).is_some() || caps.get(4).is_some() => caps,
+        _ => return Ok((due_lower, None)),
+    };
+    let mut hour = caps[2].parse::<u32>()?;
+    let minute = caps.get(3).map_or(Ok(0), |m| m.as_str().parse::<u32>())?;
+    if let Some(meridiem) = caps.get(4) {
+        if !(1..=12).contains(&hour) {
+            anyhow::bail!("Wrong hour!");
+        }
+        hour = match meridiem.as_str() {
+            "am" => hour % 12,
+            _ => hour % 12 + 12,
+        };
+    }
+    match NaiveTime::from_hms_opt(hour, minute, 0) {
+        Some(time) => Ok((caps[1].trim().to_string(), Some(time))),
+        None => anyhow::bail!("Wrong time!"),
     }
 }
 
-// Returns the next date matching a certain weekday
-fn get_next_weekday(day_str: &str) -> Result<NaiveDate> {
-    let day = day_str.parse::<Weekday>()?; // Day we aim to get
-    let now = Local::now();
-    let today = now.weekday(); // Day we start from
-                               // We initialize with the next day
-    let mut next_day = today.succ();
+// Converts a due date with an optional time of day, a time alone is due today
+pub fn convert_str_due(due_str: &str) -> Result<(NaiveDate, Option<NaiveTime>)> {
+    let (date_str, time) = split_due_time(due_str)?;
+    match (date_str.as_str(), time) {
+        ("", Some(time)) => Ok((Local::now().date_naive(), Some(time))),
+        _ => Ok((convert_str_valid_date(&date_str)?, time)),
+    }
+}
 
-    let mut counter = 1;
-    while next_day != day {
-        // Loop until we find the correct weekday, deducing the gap
-        counter += 1;
-        next_day = next_day.succ();
+// Validates a due date typed by the user, empty strings are allowed
+pub fn validate_due_date(s: &str) -> Result<(), &'static str> {
+    match s.trim().is_empty() || convert_str_due(s).is_ok() {
+        true => Ok(()),
+        false => Err("Invalid date!"),
     }
-    // Returns the new date with the correct shift
-    Ok((now + Duration::days(counter)).date_naive())
+}
+
+// Returns the next date matching a certain weekday, a week from now for today's weekday
+fn get_next_weekday(day: Weekday) -> NaiveDate {
+    let today = Local::now().date_naive();
+    let gap = (day.num_days_from_monday() + 6 - today.weekday().num_days_from_monday()) % 7 + 1;
+    today + Duration::days(gap as i64)
 }
 
 // Handles special language date markers
 fn get_language_date(due_date: &str) -> Result<NaiveDate> {
     let now: DateTime<Local> = Local::now();
+    let today = now.date_naive();
     let ret = match due_date {
-        "today" => now.date_naive(),
-        "tomorrow" => (now + Duration::days(1)).date_naive(),
-        "next week" => (now + Duration::days(7)).date_naive(),
+        "today" => today,
+        "tomorrow" => today + Duration::days(1),
+        "next week" => today + Duration::days(7),
         "next month" => {
             let new_date = now.checked_add_months(chrono::Months::new(1));
             match new_date {
                 Some(v) => v.date_naive(),
-                None => (now + Duration::days(30)).date_naive(),
+                None => today + Duration::days(30),
             }
         }
+        // Weeks end on sunday
+        "end of week" | "eow" => {
+            today + Duration::days(6 - today.weekday().num_days_from_monday() as i64)
+        }
+        "end of month" | "eom" => {
+            let month_start = today.with_day(1).unwrap_or(today);
+            month_start + Months::new(1) - Duration::days(1)
+        }
         _ => anyhow::bail!("Error in natural language parsing for date (this should not happen)"),
     };
     Ok(ret)
 }
+
+// Handles offsets from today: "in 3 days", "in 2 weeks", "in 1 month", "+3d", "+2w", "+1m"
+fn get_offset_date(due_str: &str) -> Option<NaiveDate> {
+    static REOFFSET: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"^(?:in\s+|\+)(\d+)\s*(d|days?|w|weeks?|m|months?)$").unwrap());
+    let caps = REOFFSET.captures(due_str)?;
+    let count = caps[1].parse::<u32>().ok()?;
+    let today = Local::now().date_naive();
+    match &caps[2][..1] {
+        "d" => today.checked_add_signed(Duration::days(count as i64)),
+        "w" => today.checked_add_signed(Duration::weeks(count as i64)),
+        _ => today.checked_add_months(Months::new(count)),
+    }
+}
+
+// Handles YYYY-mm-dd and the short dates in the order of the format, dd-mm(-YYYY) or mm-dd(-YYYY)
+// The current year is used when omitted
+fn get_numeric_date(due_str: &str, format: DateFormat) -> Option<NaiveDate> {
+    static RESHORT: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"^(\d{1,2})-(\d{1,2})(?:-(\d{4}))?$").unwrap());
+    static REISO: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\d{4})-(\d{1,2})-(\d{1,2})$").unwrap());
+    if let Some(caps) = REISO.captures(due_str) {
+        return NaiveDate::from_ymd_opt(
+            caps[1].parse().ok()?,
+            caps[2].parse().ok()?,
+            caps[3].parse().ok()?,
+        );
+    }
+    let caps = RESHORT.captures(due_str)?;
+    let year = match caps.get(3) {
+        Some(year) => year.as_str().parse().ok()?,
+        None => Local::now().year(),
+    };
+    let (first, second) = (caps[1].parse().ok()?, caps[2].parse().ok()?);
+    match format {
+        DateFormat::DayMonth => NaiveDate::from_ymd_opt(year, second, first),
+        DateFormat::MonthDay | DateFormat::Iso => NaiveDate::from_ymd_opt(year, first, second),
+    }
+}
+
+// Handles dates with a month name: "5 nov", "5th november 2027", "november 5th", "nov 5, 2027"
+// The current year is used when omitted
+fn get_month_name_date(due_str: &str) -> Option<NaiveDate> {
+    static REDAYFIRST: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"^(\d{1,2})(?:st|nd|rd|th)?\s+([a-z]+)\.?,?(?:\s+(\d{4}))?$").unwrap()
+    });
+    static REMONTHFIRST: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"^([a-z]+)\.?\s+(\d{1,2})(?:st|nd|rd|th)?,?(?:\s+(\d{4}))?$").unwrap()
+    });
+    let (day, month, year) = match REDAYFIRST.captures(due_str) {
+        Some(caps) => (caps.get(1)?, caps.get(2)?, caps.get(3)),
+        None => {
+            let caps = REMONTHFIRST.captures(due_str)?;
+            (caps.get(2)?, caps.get(1)?, caps.get(3))
+        }
+    };
+    let month = get_month_number(month.as_str())?;
+    let year = match year {
+        Some(year) => year.as_str().parse().ok()?,
+        None => Local::now().year(),
+    };
+    NaiveDate::from_ymd_opt(year, month, day.as_str().parse().ok()?)
+}
+
+// Month number of a month name or of an abbreviation of at least 3 letters
+fn get_month_number(name: &str) -> Option<u32> {
+    if name.len() < 3 {
+        return None;
+    }
+    MONTHS
+        .iter()
+        .position(|month| month.starts_with(name))
+        .map(|idx| idx as u32 + 1)
+}
+
+// Parses a recurrence rule: "every day", "every 3 days", "every monday,friday",
+// "every month on 15", as well as the shortcuts "daily", "weekly" and "monthly (on 15)"
+pub fn convert_str_recurrence(rule_str: &str) -> Result<Recurrence> {
+    let rule = rule_str.trim();
+    let body = match rule.strip_prefix("every ") {
+        Some(body) => body.trim().to_string(),
+        None => match rule {
+            "daily" => "day".to_string(),
+            "weekly" => "week".to_string(),
+            _ => match rule.strip_prefix("monthly") {
+                Some(rest) => format!("month{}", rest),
+                None => anyhow::bail!("Not a recurrence rule!"),
+            },
+        },
+    };
+    let today = Local::now().date_naive();
+    let recurrence = match body.as_str() {
+        "day" => Recurrence::Daily,
+        "week" => Recurrence::Weekly(vec![today.weekday()]),
+        "month" => Recurrence::Monthly(today.day()),
+        _ => {
+            if let Some(day_str) = body.strip_prefix("month on ") {
+                let day = day_str
+                    .trim()
+                    .trim_end_matches(|c: char| c.is_alphabetic())
+                    .parse::<u32>()?;
+                if !(1..=31).contains(&day) {
+                    anyhow::bail!("Wrong day of month!");
+                }
+                Recurrence::Monthly(day)
+            } else if let Some(n_str) = body
+                .strip_suffix(" days")
+                .or_else(|| body.strip_suffix(" day"))
+            {
+                match n_str.trim().parse::<u32>()? {
+                    0 => anyhow::bail!("Wrong number of days!"),
+                    1 => Recurrence::Daily,
+                    n => Recurrence::EveryNDays(n),
+                }
+            } else {
+                let mut days = body
+                    .replace(" and ", ",")
+                    .split(',')
+                    .map(|day| day.trim().parse::<Weekday>())
+                    .collect::<Result<Vec<Weekday>, _>>()?;
+                days.sort_by_key(|day| day.num_days_from_monday());
+                days.dedup();
+                Recurrence::Weekly(days)
+            }
+        }
+    };
+    Ok(recurrence)
+}
+
+// Returns the first date of a recurrence, starting from today
+pub fn get_first_occurrence(recurrence: &Recurrence) -> NaiveDate {
+    let today = Local::now().date_naive();
+    match recurrence {
+        Recurrence::EveryNDays(_) => today,
+        _ => get_next_occurrence(recurrence, today - Duration::days(1)),
+    }
+}
+
+// Returns the first date of a recurrence strictly after the given date
+pub fn get_next_occurrence(recurrence: &Recurrence, after: NaiveDate) -> NaiveDate {
+    match recurrence {
+        Recurrence::Daily => after + Duration::days(1),
+        Recurrence::EveryNDays(n) => after + Duration::days(*n as i64),
+        Recurrence::Weekly(days) if days.is_empty() => after + Duration::days(7),
+        Recurrence::Weekly(days) => {
+            let mut next = after + Duration::days(1);
+            while !days.contains(&next.weekday()) {
+                next += Duration::days(1);
+            }
+            next
+        }
+        Recurrence::Monthly(day) => {
+            let mut month_start = after.with_day(1).unwrap_or(after);
+            loop {
+                // Days beyond the end of the month are clamped to the last day
+                let candidate = (1..=*day)
+                    .rev()
+                    .find_map(|d| month_start.with_day(d))
+                    .unwrap_or(month_start);
+                if candidate > after {
+                    return candidate;
+                }
+                month_start = month_start + Months::new(1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn time(hour: u32, minute: u32) -> Option<NaiveTime> {
+        NaiveTime::from_hms_opt(hour, minute, 0)
+    }
+
+    #[test]
+    fn converts_language_dates() {
+        let today = Local::now().date_naive();
+        assert_eq!(convert_str_valid_date("today").unwrap(), today);
+        assert_eq!(
+            convert_str_valid_date(" Tomorrow ").unwrap(),
+            today + Duration::days(1)
+        );
+        assert_eq!(
+            convert_str_valid_date("next week").unwrap(),
+            today + Duration::days(7)
+        );
+        assert_eq!(
+            convert_str_valid_date("next month").unwrap(),
+            today + Months::new(1)
+        );
+        let end_of_week = convert_str_valid_date("eow").unwrap();
+        assert_eq!(end_of_week.weekday(), Weekday::Sun);
+        assert!(end_of_week >= today && end_of_week < today + Duration::days(7));
+        assert_eq!(convert_str_valid_date("end of week").unwrap(), end_of_week);
+        let end_of_month = convert_str_valid_date("eom").unwrap();
+        assert_eq!(end_of_month.month(), today.month());
+        assert_eq!((end_of_month + Duration::days(1)).day(), 1);
+        assert_eq!(
+            convert_str_valid_date("end of month").unwrap(),
+            end_of_month
+        );
+    }
+
+    #[test]
+    fn converts_weekdays() {
+        let today = Local::now().date_naive();
+        for day in ["friday", "next friday", "fri"] {
+            let friday = convert_str_valid_date(day).unwrap();
+            assert_eq!(friday.weekday(), Weekday::Fri);
+            assert!(friday > today && friday <= today + Duration::days(7));
+        }
+    }
+
+    #[test]
+    fn converts_offsets() {
+        let today = Local::now().date_naive();
+        for (offset, days) in [("in 3 days", 3), ("+3d", 3), ("in 1 day", 1), ("+0d", 0)] {
+            assert_eq!(
+                convert_str_valid_date(offset).unwrap(),
+                today + Duration::days(days)
+            );
+        }
+        assert_eq!(
+            convert_str_valid_date("in 2 weeks").unwrap(),
+            today + Duration::weeks(2)
+        );
+        assert_eq!(
+            convert_str_valid_date("+2w").unwrap(),
+            today + Duration::weeks(2)
+        );
+        assert_eq!(
+            convert_str_valid_date("+1m").unwrap(),
+            today + Months::new(1)
+        );
+        assert_eq!(
+            convert_str_valid_date("in 13 months").unwrap(),
+            today + Months::new(13)
+        );
+        assert!(convert_str_valid_date("in three days").is_err());
+        assert!(convert_str_valid_date("+3y").is_err());
+    }
+
+    #[test]
+    fn converts_numeric_dates() {
+        let year = Local::now().year();
+        // ISO dates do not depend on DATE_FORMAT
+        assert_eq!(
+            convert_str_valid_date("2027-11-05").unwrap(),
+            date(2027, 11, 5)
+        );
+        assert!(convert_str_valid_date("2027-13-01").is_err());
+        let day_month = |due| get_numeric_date(due, DateFormat::DayMonth);
+        assert_eq!(day_month("5-11-2027"), Some(date(2027, 11, 5)));
+        assert_eq!(day_month("05-11"), Some(date(year, 11, 5)));
+        assert_eq!(day_month("29-02-2028"), Some(date(2028, 2, 29)));
+        assert_eq!(day_month("29-02-2027"), None);
+        assert_eq!(day_month("31-04-2027"), None);
+        assert_eq!(day_month("32-01"), None);
+        let month_day = |due| get_numeric_date(due, DateFormat::MonthDay);
+        assert_eq!(month_day("11-5-2027"), Some(date(2027, 11, 5)));
+        assert_eq!(month_day("11-05"), Some(date(year, 11, 5)));
+        assert_eq!(month_day("05-11"), Some(date(year, 5, 11)));
+        assert_eq!(month_day("13-01"), None);
+        assert_eq!(
+            get_numeric_date("2027-11-05", DateFormat::MonthDay),
+            Some(date(2027, 11, 5))
+        );
+    }
+
+    #[test]
+    fn converts_month_names() {
+        let year = Local::now().year();
+        for due in [
+            "5 nov 2027",
+            "5th november 2027",
+            "november 5th, 2027",
+            "nov 5, 2027",
+            "Nov. 5 2027",
+        ] {
+            assert_eq!(convert_str_valid_date(due).unwrap(), date(2027, 11, 5));
+        }
+        assert_eq!(convert_str_valid_date("1st jan").unwrap(), date(year, 1, 1));
+        assert_eq!(
+            convert_str_valid_date("29 feb 2028").unwrap(),
+            date(2028, 2, 29)
+        );
+        assert!(convert_str_valid_date("29 feb 2027").is_err());
+        assert!(convert_str_valid_date("31 apr 2027").is_err());
+        assert!(convert_str_valid_date("5 no").is_err());
+        assert!(convert_str_valid_date("5 novembre").is_err());
+    }
+
+    #[test]
+    fn converts_recurrence_rules() {
+        let today = Local::now().date_naive();
+        assert_eq!(convert_str_valid_date("daily").unwrap(), today);
+        assert_eq!(convert_str_valid_date("every 3 days").unwrap(), today);
+        assert_eq!(
+            convert_str_recurrence("every monday,friday").unwrap(),
+            Recurrence::Weekly(vec![Weekday::Mon, Weekday::Fri])
+        );
+        assert_eq!(
+            convert_str_recurrence("every friday and monday").unwrap(),
+            Recurrence::Weekly(vec![Weekday::Mon, Weekday::Fri])
+        );
+        assert_eq!(
+            convert_str_recurrence("monthly on 15th").unwrap(),
+            Recurrence::Monthly(15)
+        );
+        assert_eq!(
+            convert_str_recurrence("every 1 day").unwrap(),
+            Recurrence::Daily
+        );
+        assert!(convert_str_recurrence("every month on 32").is_err());
+        assert!(convert_str_recurrence("every 0 days").is_err());
+        assert!(convert_str_recurrence("every someday").is_err());
+        assert!(convert_str_valid_date("someday").is_err());
+        assert!(convert_str_valid_date("").is_err());
+    }
+
+    #[test]
+    fn splits_due_times() {
+        assert_eq!(
+            split_due_time("tomorrow 14:00").unwrap(),
+            ("tomorrow".to_string(), time(14, 0))
+        );
+        assert_eq!(
+            split_due_time("25-10 9am").unwrap(),
+            ("25-10".to_string(), time(9, 0))
+        );
+        assert_eq!(
+            split_due_time("Friday at 5:30PM").unwrap(),
+            ("friday".to_string(), time(17, 30))
+        );
+        assert_eq!(split_due_time("12am").unwrap(), (String::new(), time(0, 0)));
+        assert_eq!(
+            split_due_time("12pm").unwrap(),
+            (String::new(), time(12, 0))
+        );
+        // Bare numbers are days, not hours
+        assert_eq!(
+            split_due_time("nov 5").unwrap(),
+            ("nov 5".to_string(), None)
+        );
+        assert_eq!(
+            split_due_time("in 3 days").unwrap(),
+            ("in 3 days".to_string(), None)
+        );
+        assert!(split_due_time("13pm").is_err());
+        assert!(split_due_time("0am").is_err());
+        assert!(split_due_time("25:00").is_err());
+        assert!(split_due_time("14:60").is_err());
+        assert_eq!(
+            convert_str_due("18:30").unwrap(),
+            (Local::now().date_naive(), time(18, 30))
+        );
+    }
+
+    #[test]
+    fn gets_next_occurrences() {
+        assert_eq!(
+            get_next_occurrence(&Recurrence::Daily, date(2027, 2, 28)),
+            date(2027, 3, 1)
+        );
+        assert_eq!(
+            get_next_occurrence(&Recurrence::EveryNDays(3), date(2026, 12, 30)),
+            date(2027, 1, 2)
+        );
+        // 2026-10-16 is a friday
+        let weekly = Recurrence::Weekly(vec![Weekday::Mon, Weekday::Fri]);
+        assert_eq!(
+            get_next_occurrence(&weekly, date(2026, 10, 16)),
+            date(2026, 10, 19)
+        );
+        assert_eq!(
+            get_next_occurrence(&weekly, date(2026, 10, 19)),
+            date(2026, 10, 23)
+        );
+        assert_eq!(
+            get_next_occurrence(&Recurrence::Weekly(Vec::new()), date(2026, 10, 16)),
+            date(2026, 10, 23)
+        );
+    }
+
+    #[test]
+    fn gets_next_monthly_occurrences() {
+        let end_of_month = Recurrence::Monthly(31);
+        assert_eq!(
+            get_next_occurrence(&end_of_month, date(2027, 1, 31)),
+            date(2027, 2, 28)
+        );
+        assert_eq!(
+            get_next_occurrence(&end_of_month, date(2028, 1, 31)),
+            date(2028, 2, 29)
+        );
+        assert_eq!(
+            get_next_occurrence(&end_of_month, date(2027, 2, 28)),
+            date(2027, 3, 31)
+        );
+        assert_eq!(
+            get_next_occurrence(&end_of_month, date(2027, 4, 15)),
+            date(2027, 4, 30)
+        );
+        let mid_month = Recurrence::Monthly(15);
+        assert_eq!(
+            get_next_occurrence(&mid_month, date(2026, 12, 14)),
+            date(2026, 12, 15)
+        );
+        assert_eq!(
+            get_next_occurrence(&mid_month, date(2026, 12, 15)),
+            date(2027, 1, 15)
+        );
+    }
+}
@@ -0,0 +1,302 @@
+use crate::{
+    date_utils::convert_str_valid_date,
+    model::{Todo, MAXPRIORITY},
+};
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::cmp::Ordering;
+
+// Comparison operator of a field predicate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ne,
+    Ge,
+    Gt,
+}
+impl Comparison {
+    fn parse(op: &str) -> Option<Comparison> {
+        match op {
+            "<" => Some(Comparison::Lt),
+            "<=" => Some(Comparison::Le),
+            "=" | ":" => Some(Comparison::Eq),
+            "!=" => Some(Comparison::Ne),
+            ">=" => Some(Comparison::Ge),
+            ">" => Some(Comparison::Gt),
+            _ => None,
+        }
+    }
+    fn accepts(&self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Lt => ordering == Ordering::Less,
+            Comparison::Le => ordering != Ordering::Greater,
+            Comparison::Eq => ordering == Ordering::Equal,
+            Comparison::Ne => ordering != Ordering::Equal,
+            Comparison::Ge => ordering != Ordering::Less,
+            Comparison::Gt => ordering == Ordering::Greater,
+        }
+    }
+}
+
+// Single condition on the fields of a TODO
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    Done,
+    Recurring,
+    NoDue,
+    Tag(String),
+    Text(String),
+    Priority(Comparison, u32),
+    Progress(Comparison, u32),
+    Due(Comparison, NaiveDate),
+    Scheduled(Comparison, NaiveDate),
+    Created(Comparison, NaiveDate),
+    Not(Box<Predicate>),
+}
+impl Predicate {
+    pub fn matches(&self, todo: &Todo) -> bool {
+        match self {
+            Predicate::Done => todo.is_complete(),
+            Predicate::Recurring => todo.get_recurrence().is_some(),
+            Predicate::NoDue => todo.get_due_date().is_none(),
+            Predicate::Tag(tag) => todo.has_tag(tag),
+            Predicate::Text(text) => todo.matches_text(text),
+            Predicate::Priority(op, value) => op.accepts(todo.get_priority().cmp(value)),
+            Predicate::Progress(op, value) => {
+                op.accepts((todo.get_progress().clone() as u32).cmp(value))
+            }
+            // TODOs without due or start date never match a comparison on this date
+            Predicate::Due(op, date) => todo
+                .get_due_date()
+                .as_ref()
+                .is_some_and(|due| op.accepts(due.get_0().cmp(date))),
+            Predicate::Scheduled(op, date) => todo
+                .get_scheduled_date()
+                .as_ref()
+                .is_some_and(|scheduled| op.accepts(scheduled.get_0().cmp(date))),
+            Predicate::Created(op, date) => {
+                op.accepts(todo.get_created_date().get_0().date().cmp(date))
+            }
+            Predicate::Not(predicate) => !predicate.matches(todo),
+        }
+    }
+}
+
+// Conjunction of predicates parsed from an expression like `due<2026-11-01 priority>=2 !done tag:work`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    source: String,
+    predicates: Vec<Predicate>,
+}
+impl Filter {
+    // Filter keeping the completed TODOs only
+    pub fn completed() -> Filter {
+        Filter {
+            source: "done".to_string(),
+            predicates: vec![Predicate::Done],
+        }
+    }
+    // Parses a filter expression, terms are separated by whitespace and all of them must match
+    pub fn parse(expression: &str) -> Result<Filter> {
+        let terms: Vec<&str> = expression.split_whitespace().collect();
+        if terms.is_empty() {
+            bail!("Empty filter!");
+        }
+        let predicates = terms
+            .iter()
+            .map(|term| {
+                parse_term(term).with_context(|| format!("Invalid filter term '{}'!", term))
+            })
+            .collect::<Result<Vec<Predicate>>>()?;
+        Ok(Filter {
+            source: terms.join(" "),
+            predicates,
+        })
+    }
+    pub fn matches(&self, todo: &Todo) -> bool {
+        self.predicates
+            .iter()
+            .all(|predicate| predicate.matches(todo))
+    }
+    // Completion state required by the filter, used to narrow down the DB query
+    pub fn get_completed(&self) -> Option<bool> {
+        self.predicates
+            .iter()
+            .find_map(|predicate| match predicate {
+                Predicate::Done => Some(true),
+                Predicate::Not(inner) if **inner == Predicate::Done => Some(false),
+                _ => None,
+            })
+    }
+}
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+// Parses a single term of a filter expression, a leading '!' negates it
+fn parse_term(term: &str) -> Result<Predicate> {
+    static RECOMPARISON: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"^([a-z]+)(<=|>=|!=|<|>|=|:)(.+)$").unwrap());
+    if let Some(inner) = term.strip_prefix('!') {
+        return Ok(Predicate::Not(Box::new(parse_term(inner)?)));
+    }
+    let term_lower = term.to_lowercase();
+    match term_lower.as_str() {
+        "" => bail!("Empty term!"),
+        "done" | "completed" => return Ok(Predicate::Done),
+        "recurring" => return Ok(Predicate::Recurring),
+        _ => (),
+    }
+    if let Some(tag) = term_lower.strip_prefix('#') {
+        return Ok(Predicate::Tag(tag.to_string()));
+    }
+    let caps = match RECOMPARISON.captures(&term_lower) {
+        Some(caps) => caps,
+        None => return Ok(Predicate::Text(term_lower)),
+    };
+    let (field, op_str, value) = (&caps[1], &caps[2], &caps[3]);
+    let op = Comparison::parse(op_str).with_context(|| "Unknown operator!")?;
+    match field {
+        "tag" if op == Comparison::Eq => {
+            Ok(Predicate::Tag(value.trim_start_matches('#').to_string()))
+        }
+        "due" if op == Comparison::Eq && value == "none" => Ok(Predicate::NoDue),
+        "due" => Ok(Predicate::Due(op, parse_filter_date(value)?)),
+        "scheduled" | "start" => Ok(Predicate::Scheduled(op, parse_filter_date(value)?)),
+        "created" => Ok(Predicate::Created(op, parse_filter_date(value)?)),
+        "priority" => {
+            let priority = value.parse::<u32>()?;
+            if priority > MAXPRIORITY {
+                bail!("Priority level above {}!", MAXPRIORITY);
+            }
+            Ok(Predicate::Priority(op, priority))
+        }
+        "progress" => {
+            let progress = value.trim_end_matches('%').parse::<u32>()?;
+            if progress > 100 {
+                bail!("Progress above 100%!");
+            }
+            Ok(Predicate::Progress(op, progress))
+        }
+        "tag" => bail!("Tags only support '=' or ':'!"),
+        // Not a field name, e.g. an URL
+        _ if value.starts_with("//") => Ok(Predicate::Text(term_lower.clone())),
+        _ => bail!("Unknown field '{}'!", field),
+    }
+}
+
+// Reads a date written in any format accepted for due dates, e.g. YYYY-mm-dd
+pub fn parse_filter_date(value: &str) -> Result<NaiveDate> {
+    convert_str_valid_date(value).with_context(|| format!("Invalid date '{}'!", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{MyDate, Progress};
+
+    // TODO due on 2026-11-05 with priority 2, tagged work and half done
+    fn get_todo() -> Todo {
+        let mut todo = Todo::new("Write the report", 2, Some("2026-11-05"));
+        todo.set_tags(vec!["work".to_string()]);
+        todo.set_progress(Progress::Half);
+        todo
+    }
+
+    fn matches(expression: &str, todo: &Todo) -> bool {
+        Filter::parse(expression).unwrap().matches(todo)
+    }
+
+    #[test]
+    fn parses_keywords() {
+        let mut todo = get_todo();
+        assert!(!matches("done", &todo));
+        assert!(matches("!done", &todo));
+        assert!(!matches("recurring", &todo));
+        todo.set_completed(true);
+        assert!(matches("done", &todo));
+        assert!(matches("completed", &todo));
+        let recurring = Todo::new("Water the plants", 0, Some("every 3 days"));
+        assert!(matches("recurring", &recurring));
+    }
+
+    #[test]
+    fn parses_tags() {
+        let todo = get_todo();
+        assert!(matches("#work", &todo));
+        assert!(matches("tag:work", &todo));
+        assert!(matches("tag=#Work", &todo));
+        assert!(!matches("tag:home", &todo));
+        assert!(Filter::parse("tag>work").is_err());
+    }
+
+    #[test]
+    fn parses_comparisons() {
+        let todo = get_todo();
+        assert!(matches("priority<3", &todo));
+        assert!(matches("priority<=2", &todo));
+        assert!(matches("priority=2", &todo));
+        assert!(matches("priority:2", &todo));
+        assert!(!matches("priority!=2", &todo));
+        assert!(!matches("priority>=3", &todo));
+        assert!(matches("priority>1", &todo));
+        assert!(matches("progress>=50%", &todo));
+        assert!(!matches("progress<50", &todo));
+    }
+
+    #[test]
+    fn parses_dates() {
+        let mut todo = get_todo();
+        assert!(matches("due<2026-12-01", &todo));
+        assert!(matches("due=2026-11-05", &todo));
+        assert!(!matches("due>2026-11-05", &todo));
+        assert!(!matches("due:none", &todo));
+        assert!(matches("due:none", &Todo::new("Someday", 0, None)));
+        assert!(!matches("scheduled<=2026-11-01", &todo));
+        todo.set_scheduled_date(Some(MyDate(NaiveDate::from_ymd_opt(2026, 11, 1).unwrap())));
+        assert!(matches("scheduled<=2026-11-01", &todo));
+        assert!(matches("start=2026-11-01", &todo));
+        assert!(matches("created>=2026-01-01", &todo));
+        assert!(!matches("created<2026-01-01", &todo));
+    }
+
+    #[test]
+    fn parses_text() {
+        let todo = get_todo();
+        assert!(matches("REPORT", &todo));
+        assert!(!matches("slides", &todo));
+        assert!(matches("!slides report", &todo));
+        assert_eq!(
+            Filter::parse("https://example.com").unwrap().predicates,
+            vec![Predicate::Text("https://example.com".to_string())]
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert!(Filter::parse("").is_err());
+        assert!(Filter::parse("   ").is_err());
+        assert!(Filter::parse("!").is_err());
+        assert!(Filter::parse("prority>=2").is_err());
+        assert!(Filter::parse("tga:work").is_err());
+        assert!(Filter::parse("priority>=4").is_err());
+        assert!(Filter::parse("priority>=high").is_err());
+        assert!(Filter::parse("progress>150").is_err());
+        assert!(Filter::parse("due<someday").is_err());
+    }
+
+    #[test]
+    fn keeps_the_source() {
+        let filter = Filter::parse("  due<today   !done ").unwrap();
+        assert_eq!(filter.to_string(), "due<today !done");
+        assert_eq!(filter.get_completed(), Some(false));
+        assert_eq!(Filter::completed().get_completed(), Some(true));
+        assert_eq!(Filter::parse("#work").unwrap().get_completed(), None);
+    }
+}
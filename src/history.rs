@@ -0,0 +1,472 @@
+use crate::{
+    migration::{migrate_record, RawRecord},
+    model::Todo,
+    storage,
+};
+use anyhow::{Context, Result};
+use humphrey_json::{prelude::*, Value};
+use jasondb::Database;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+// Number of operations kept in the journal
+const HISTORY_SIZE: usize = 100;
+// The archive of a list is tracked as "<list>/archive", '/' cannot appear in list names
+const ARCHIVE_SUFFIX: &str = "/archive";
+
+// State of a TODO before and after an operation, None when it did not exist
+// The states are kept untyped so that they can be migrated when restored
+#[derive(FromJson, IntoJson)]
+pub struct Change {
+    list: String,
+    id: String,
+    before: Option<RawRecord>,
+    after: Option<RawRecord>,
+}
+
+// Reversible operation recorded in the journal, ids are time-ordered
+// Undone operations are kept to be redone until a new operation is recorded
+#[derive(FromJson, IntoJson)]
+pub struct Operation {
+    id: String,
+    description: String,
+    changes: Vec<Change>,
+    undone: bool,
+}
+
+pub type HistoryModel = Database<Operation>;
+// Opens the DB of another list than the current one, or the archive of a list
+type Connect<'a> = &'a dyn Fn(&str) -> Result<storage::DatabaseModel>;
+
+// State of all the TODOs of some lists, taken before applying an action
+pub struct Snapshot {
+    list: String,
+    states: Vec<(String, HashMap<String, Value>)>,
+}
+impl Snapshot {
+    pub fn take(db: &mut storage::DatabaseModel, list: &str) -> Result<Snapshot> {
+        Ok(Snapshot {
+            list: list.to_string(),
+            states: vec![(list.to_string(), get_states(db)?)],
+        })
+    }
+    // State of a single TODO, for the actions which only change this TODO
+    // Avoids reading the whole list when the TODOs are already in memory
+    pub fn take_todo(list: &str, todo: &Todo) -> Snapshot {
+        Snapshot {
+            list: list.to_string(),
+            states: vec![(
+                list.to_string(),
+                HashMap::from([(todo.get_id().to_string(), todo.to_json())]),
+            )],
+        }
+    }
+    // Changes made to the TODO of a single TODO snapshot, found in the TODOs in memory
+    pub fn diff_todo(&self, todos: &[Todo]) -> Vec<Change> {
+        let mut changes = Vec::new();
+        for (list, before) in &self.states {
+            for (id, value) in before {
+                let after = todos
+                    .iter()
+                    .find(|todo| todo.get_id() == id)
+                    .map(|todo| todo.to_json());
+                if after.as_ref() != Some(value) {
+                    changes.push(Change {
+                        list: list.clone(),
+                        id: id.clone(),
+                        before: Some(RawRecord(value.clone())),
+                        after: after.map(RawRecord),
+                    });
+                }
+            }
+        }
+        changes
+    }
+    // Also tracks another list, e.g. the target of a move
+    pub fn add_list(&mut self, list: &str) -> Result<()> {
+        if self.states.iter().all(|(name, _)| name != list) {
+            let states = get_states(&mut connect_store(list)?)?;
+            self.states.push((list.to_string(), states));
+        }
+        Ok(())
+    }
+    // Also tracks the archive of a list
+    pub fn add_archive(&mut self, list: &str) -> Result<()> {
+        self.add_list(&format!("{}{}", list, ARCHIVE_SUFFIX))
+    }
+    // Changes made to the tracked lists since the snapshot was taken
+    pub fn diff(&self, db: &mut storage::DatabaseModel) -> Result<Vec<Change>> {
+        let mut changes = Vec::new();
+        for (list, before) in &self.states {
+            let after = match *list == self.list {
+                true => get_states(db)?,
+                false => get_states(&mut connect_store(list)?)?,
+            };
+            for (id, value) in &after {
+                if before.get(id) != Some(value) {
+                    changes.push(Change {
+                        list: list.clone(),
+                        id: id.clone(),
+                        before: before.get(id).cloned().map(RawRecord),
+                        after: Some(RawRecord(value.clone())),
+                    });
+                }
+            }
+            for (id, value) in before {
+                if !after.contains_key(id) {
+                    changes.push(Change {
+                        list: list.clone(),
+                        id: id.clone(),
+                        before: Some(RawRecord(value.clone())),
+                        after: None,
+                    });
+                }
+            }
+        }
+        Ok(changes)
+    }
+}
+
+fn get_states(db: &mut storage::DatabaseModel) -> Result<HashMap<String, Value>> {
+    Ok(storage::get_todos(db)?
+        .into_iter()
+        .map(|(id, todo)| (id, todo.to_json()))
+        .collect())
+}
+
+// Connects to a list or to the archive of a list
+fn connect_store(name: &str) -> Result<storage::DatabaseModel> {
+    match name.strip_suffix(ARCHIVE_SUFFIX) {
+        Some(list) => storage::connect_archive(list),
+        None => storage::connect_db(name),
+    }
+}
+
+fn connect_history() -> Result<HistoryModel> {
+    let history: HistoryModel = Database::new(storage::get_location_history()?)
+        .with_context(|| "Error opening history!")?;
+    Ok(history)
+}
+
+// All the operations of the journal, oldest first
+fn get_operations(history: &mut HistoryModel) -> Vec<Operation> {
+    let mut operations = history
+        .iter()
+        .filter_map(|x| x.ok())
+        .map(|(_, operation)| operation)
+        .collect::<Vec<Operation>>();
+    operations.sort_by(|a, b| a.id.cmp(&b.id));
+    operations
+}
+
+// Records the changes of an action in the journal, nothing is recorded without change
+// The operations which were undone cannot be redone anymore
+pub fn record(changes: Vec<Change>) -> Result<()> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    record_in(&mut connect_history()?, changes)
+}
+
+fn record_in(history: &mut HistoryModel, changes: Vec<Change>) -> Result<()> {
+    let operations = get_operations(history);
+    let (undone, done): (Vec<Operation>, Vec<Operation>) = operations
+        .into_iter()
+        .partition(|operation| operation.undone);
+    let pruned = done.len().saturating_sub(HISTORY_SIZE - 1);
+    for operation in undone.iter().chain(done.iter().take(pruned)) {
+        history
+            .delete(&operation.id)
+            .with_context(|| "Error updating history!")?;
+    }
+    let operation = Operation {
+        id: Uuid::now_v7().to_string(),
+        description: describe_changes(&changes),
+        changes,
+        undone: false,
+    };
+    history
+        .set(&operation.id, &operation)
+        .with_context(|| "Error updating history!")?;
+    Ok(())
+}
+
+// Reverts the last operation, returns its description or None if there is nothing to undo
+// The journal is shared by all the lists: the last operation is undone in the lists it changed,
+// which are not necessarily the current one
+pub fn undo(db: &mut storage::DatabaseModel, list: &str) -> Result<Option<String>> {
+    undo_in(&mut connect_history()?, db, list, &connect_store)
+}
+
+fn undo_in(
+    history: &mut HistoryModel,
+    db: &mut storage::DatabaseModel,
+    list: &str,
+    connect: Connect,
+) -> Result<Option<String>> {
+    let operation = get_operations(history)
+        .into_iter()
+        .rev()
+        .find(|operation| !operation.undone);
+    match operation {
+        Some(mut operation) => {
+            apply_changes(db, list, &operation.changes, true, connect)?;
+            operation.undone = true;
+            history
+                .set(&operation.id, &operation)
+                .with_context(|| "Error updating history!")?;
+            Ok(Some(operation.description))
+        }
+        None => Ok(None),
+    }
+}
+
+// Applies again the last undone operation, returns its description or None if there is nothing to redo
+pub fn redo(db: &mut storage::DatabaseModel, list: &str) -> Result<Option<String>> {
+    redo_in(&mut connect_history()?, db, list, &connect_store)
+}
+
+fn redo_in(
+    history: &mut HistoryModel,
+    db: &mut storage::DatabaseModel,
+    list: &str,
+    connect: Connect,
+) -> Result<Option<String>> {
+    let operation = get_operations(history)
+        .into_iter()
+        .find(|operation| operation.undone);
+    match operation {
+        Some(mut operation) => {
+            apply_changes(db, list, &operation.changes, false, connect)?;
+            operation.undone = false;
+            history
+                .set(&operation.id, &operation)
+                .with_context(|| "Error updating history!")?;
+            Ok(Some(operation.description))
+        }
+        None => Ok(None),
+    }
+}
+
+// Restores the state of each changed TODO from before (undo) or after (redo) the operation
+// Changes to other lists than the current one are applied on their own DB
+fn apply_changes(
+    db: &mut storage::DatabaseModel,
+    list: &str,
+    changes: &[Change],
+    restore_before: bool,
+    connect: Connect,
+) -> Result<()> {
+    for change in changes {
+        let state = match restore_before {
+            true => &change.before,
+            false => &change.after,
+        };
+        let mut other_db;
+        let target_db = match change.list == list {
+            true => &mut *db,
+            false => {
+                other_db = connect(&change.list)?;
+                &mut other_db
+            }
+        };
+        match state {
+            Some(RawRecord(value)) => {
+                let mut value = value.clone();
+                migrate_record(&mut value)?;
+                let todo = Todo::from_json(&value)
+                    .map_err(|_| anyhow::anyhow!("Corrupted history entry {}!", change.id))?;
+                storage::insert_todo(target_db, &todo)?;
+            }
+            None => storage::delete_todo_by_id(target_db, &change.id)?,
+        }
+    }
+    Ok(())
+}
+
+// Summarizes the changes of an operation, e.g. "Deleted 'Write report'"
+fn describe_changes(changes: &[Change]) -> String {
+    match changes {
+        // The same TODO removed from a list and added to another one
+        [first, second] if first.id == second.id => {
+            let destination = match first.after.is_some() {
+                true => first,
+                false => second,
+            };
+            let verb = match destination.list.ends_with(ARCHIVE_SUFFIX) {
+                true => "Archived",
+                false
+                    if first.list.ends_with(ARCHIVE_SUFFIX)
+                        || second.list.ends_with(ARCHIVE_SUFFIX) =>
+                {
+                    "Restored"
+                }
+                false => "Moved",
+            };
+            format!("{} '{}'", verb, get_change_title(destination))
+        }
+        [change] => {
+            let verb = match (&change.before, &change.after) {
+                (None, _) => "Added",
+                (_, None) => "Deleted",
+                _ => "Edited",
+            };
+            format!("{} '{}'", verb, get_change_title(change))
+        }
+        _ => format!("Changed {} TODOs", changes.len()),
+    }
+}
+
+fn get_change_title(change: &Change) -> String {
+    change
+        .after
+        .as_ref()
+        .or(change.before.as_ref())
+        .and_then(|RawRecord(value)| get_title(value))
+        .unwrap_or_default()
+}
+
+fn get_title(value: &Value) -> Option<String> {
+    match value {
+        Value::Object(fields) => fields.iter().find_map(|(key, value)| match (key, value) {
+            (key, Value::String(title)) if key == "title" => Some(title.clone()),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn open_store(dir: &Path, name: &str) -> storage::DatabaseModel {
+        Database::new(dir.join(format!("{}.json", name.replace('/', "_")))).unwrap()
+    }
+
+    fn get_titles(db: &mut storage::DatabaseModel) -> Vec<String> {
+        let mut titles: Vec<String> = storage::get_todos(db)
+            .unwrap()
+            .into_iter()
+            .map(|(_, todo)| todo.get_title().to_string())
+            .collect();
+        titles.sort();
+        titles
+    }
+
+    // Applies a change to the list and records it, as the actions do
+    fn record_action(
+        history: &mut HistoryModel,
+        db: &mut storage::DatabaseModel,
+        action: impl FnOnce(&mut storage::DatabaseModel),
+    ) {
+        let snapshot = Snapshot::take(db, "work").unwrap();
+        action(db);
+        record_in(history, snapshot.diff(db).unwrap()).unwrap();
+    }
+
+    fn add(history: &mut HistoryModel, db: &mut storage::DatabaseModel, title: &str) -> Todo {
+        let todo = Todo::new(title, 0, None);
+        record_action(history, db, |db| storage::insert_todo(db, &todo).unwrap());
+        todo
+    }
+
+    #[test]
+    fn undoes_and_redoes_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let connect = |name: &str| Ok(open_store(dir.path(), name));
+        let mut history: HistoryModel = Database::new(dir.path().join("history.json")).unwrap();
+        let mut db = open_store(dir.path(), "work");
+        let mut todo = add(&mut history, &mut db, "Write report");
+        record_action(&mut history, &mut db, |db| {
+            todo.set_title("Write the report");
+            storage::update_todo(db, &todo).unwrap()
+        });
+        add(&mut history, &mut db, "Buy milk");
+
+        let mut undo = || undo_in(&mut history, &mut db, "work", &connect).unwrap();
+        assert_eq!(undo(), Some("Added 'Buy milk'".to_string()));
+        assert_eq!(undo(), Some("Edited 'Write the report'".to_string()));
+        assert_eq!(get_titles(&mut db), ["Write report"]);
+
+        let mut redo = || redo_in(&mut history, &mut db, "work", &connect).unwrap();
+        assert_eq!(redo(), Some("Edited 'Write the report'".to_string()));
+        assert_eq!(redo(), Some("Added 'Buy milk'".to_string()));
+        assert_eq!(redo(), None);
+        assert_eq!(get_titles(&mut db), ["Buy milk", "Write the report"]);
+
+        for _ in 0..3 {
+            assert!(undo_in(&mut history, &mut db, "work", &connect)
+                .unwrap()
+                .is_some());
+        }
+        assert_eq!(
+            undo_in(&mut history, &mut db, "work", &connect).unwrap(),
+            None
+        );
+        assert!(get_titles(&mut db).is_empty());
+    }
+
+    #[test]
+    fn clears_redo_after_a_new_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let connect = |name: &str| Ok(open_store(dir.path(), name));
+        let mut history: HistoryModel = Database::new(dir.path().join("history.json")).unwrap();
+        let mut db = open_store(dir.path(), "work");
+        add(&mut history, &mut db, "Write report");
+        add(&mut history, &mut db, "Buy milk");
+        undo_in(&mut history, &mut db, "work", &connect).unwrap();
+        add(&mut history, &mut db, "Call the bank");
+
+        assert_eq!(
+            redo_in(&mut history, &mut db, "work", &connect).unwrap(),
+            None
+        );
+        assert_eq!(get_titles(&mut db), ["Call the bank", "Write report"]);
+        assert_eq!(
+            undo_in(&mut history, &mut db, "work", &connect).unwrap(),
+            Some("Added 'Call the bank'".to_string())
+        );
+        assert_eq!(
+            undo_in(&mut history, &mut db, "work", &connect).unwrap(),
+            Some("Added 'Write report'".to_string())
+        );
+    }
+
+    #[test]
+    fn undoes_changes_of_other_lists() {
+        let dir = tempfile::tempdir().unwrap();
+        let connect = |name: &str| Ok(open_store(dir.path(), name));
+        let mut history: HistoryModel = Database::new(dir.path().join("history.json")).unwrap();
+        let mut db = open_store(dir.path(), "work");
+        add(&mut history, &mut db, "Write report");
+        // Last operation made in another list
+        let mut home = open_store(dir.path(), "home");
+        let todo = Todo::new("Buy milk", 0, None);
+        storage::insert_todo(&mut home, &todo).unwrap();
+        let change = Change {
+            list: "home".to_string(),
+            id: todo.get_id().to_string(),
+            before: None,
+            after: Some(RawRecord(todo.to_json())),
+        };
+        record_in(&mut history, vec![change]).unwrap();
+
+        // The journal is shared: undoing from the work list reverts the change of the home list
+        assert_eq!(
+            undo_in(&mut history, &mut db, "work", &connect).unwrap(),
+            Some("Added 'Buy milk'".to_string())
+        );
+        assert!(get_titles(&mut open_store(dir.path(), "home")).is_empty());
+        assert_eq!(get_titles(&mut db), ["Write report"]);
+        assert_eq!(
+            redo_in(&mut history, &mut db, "work", &connect).unwrap(),
+            Some("Added 'Buy milk'".to_string())
+        );
+        assert_eq!(
+            get_titles(&mut open_store(dir.path(), "home")),
+            ["Buy milk"]
+        );
+    }
+}
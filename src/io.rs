@@ -1,28 +1,44 @@
 use crate::{
-    date_utils::{validate_regex, FORMAT_DATE},
-    model::{Action, KeyEvent, MyDate, SortingMethod, Todo},
+    date_utils::{
+        convert_str_due, convert_str_recurrence, convert_str_valid_date, format_date,
+        format_datetime, format_due_date, split_due_time, validate_due_date, DateFormat,
+        FORMAT_TIME,
+    },
+    filter::Filter,
+    keymap::{Bindings, Keymap, Lookup},
+    md_utils::ImportError,
+    model::{
+        format_tags, Action, DueStatus, KeyEvent, MyDate, MyTime, PageMove, Recurrence, SortEvent,
+        SortingMethod, Subtask, Todo, PROGRESS_LEVELS,
+    },
+    storage::validate_list_name,
+    theme::{get_theme, RowField},
+    view::View,
     Progress, MAXPRIORITY,
 };
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use chrono::Local;
-use console::{style, Key, StyledObject, Term};
-use dialoguer::{theme::ColorfulTheme, Input, Select};
-use std::cmp::Ordering;
+use console::{
+    measure_text_width, pad_str, strip_ansi_codes, style, truncate_str, Alignment, Key,
+    StyledObject, Term,
+};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+use std::{cmp::Ordering, env, fs, process::Command};
 
-// Menu constant
-const MENU: &str = "
+// Menus of the archive browser, of the edit form and of the full-screen prompts, the menus of the list screen are generated from the keymap
+const ARCHIVE_MENU: &str = "
 -------------------------------------------------------------------------------------------
-a: add      m: export to markdown\t
-e: edit     x: toggle read/unread\t
-s: sort     \u{00B1}: change priority\t
-z: delete   Z: delete all completed\t
-\u{21B5}: exit     \u{023f4}\u{023f5}: change progress";
-const NERASE: usize = 5;
+r: restore   v: view details   /: search   Esc: clear search   PgUp/PgDn/Home/End: scroll   \u{21B5}: back";
+const FORM_MENU: &str = "Tab/\u{2191}\u{2193}: next field   \u{21B5}: save   Esc: cancel";
+const CONFIRM_MENU: &str = "y: yes   n/\u{21B5}: no";
+const SORT_PROMPT: &str = "Press a key of the sort menu";
+const MINPREFIX: usize = 8;
+const PROGRESS_WIDTH: usize = 8;
 
 // Prompts user for title
 pub fn input_title(prewrite: Option<&str>) -> Result<String> {
     let input = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt("Title (leave empty to go back): ")
+        .with_prompt("Title, with optional #tags (leave empty to go back): ")
         .with_initial_text(prewrite.unwrap_or("").to_string())
         .allow_empty(true)
         .interact_text()
@@ -30,20 +46,179 @@ pub fn input_title(prewrite: Option<&str>) -> Result<String> {
     Ok(input)
 }
 
-// Prompts user for due date
-pub fn input_due_date(prewrite: &Option<MyDate>) -> Result<String> {
-    let prewrite_str = match prewrite {
-        Some(MyDate(date)) => date.format(FORMAT_DATE).to_string(),
-        None => "".to_string(),
+// Prompts user for due date, the resolved date is previewed while typing
+// A recurring TODO is prewritten with its recurrence rule, escape cancels and keeps the prewritten date
+pub fn input_due_date(
+    prewrite: &Option<MyDate>,
+    prewrite_time: &Option<MyTime>,
+    recurrence: &Option<Recurrence>,
+) -> Result<String> {
+    let term = Term::stdout();
+    let prewrite = get_due_prewrite(prewrite, prewrite_time, recurrence);
+    let mut input: Vec<char> = prewrite.chars().collect();
+    let mut cursor = input.len();
+    term.write_line(
+        &style(format!(
+            "{}, YYYY-mm-dd, 5 nov, tomorrow, friday, in 3 days, +2w, eom, every ... [14:00, 9am]",
+            DateFormat::get().get_hint()
+        ))
+        .dim()
+        .to_string(),
+    )
+    .with_context(|| "Error writing line!")?;
+    loop {
+        let text: String = input.iter().collect();
+        let prompt = format!("{} {} ", style("?").yellow(), style("Due date:").bold());
+        let (prompt_rows, cursor_row) =
+            write_due_date_prompt(&term, &prompt, &input, cursor, &get_due_date_preview(&text))?;
+        let key = term.read_key().with_context(|| "Error reading key!")?;
+        // Rows written from the preview to the last row of the input, the cursor is on one of them
+        term.move_cursor_down(prompt_rows - 1 - cursor_row)
+            .with_context(|| "Error moving cursor!")?;
+        term.clear_line().with_context(|| "Error clearing line!")?;
+        term.clear_last_lines(prompt_rows - 1)
+            .with_context(|| "Error clearing lines!")?;
+        let text = match key {
+            Key::Enter if validate_due_date(&text).is_ok() => text,
+            Key::Escape => prewrite.clone(),
+            Key::Backspace if cursor > 0 => {
+                cursor -= 1;
+                input.remove(cursor);
+                continue;
+            }
+            Key::Del if cursor < input.len() => {
+                input.remove(cursor);
+                continue;
+            }
+            Key::ArrowLeft => {
+                cursor = cursor.saturating_sub(1);
+                continue;
+            }
+            Key::ArrowRight => {
+                cursor = (cursor + 1).min(input.len());
+                continue;
+            }
+            Key::Home => {
+                cursor = 0;
+                continue;
+            }
+            Key::End => {
+                cursor = input.len();
+                continue;
+            }
+            Key::Char(c) if !c.is_control() => {
+                input.insert(cursor, c);
+                cursor += 1;
+                continue;
+            }
+            _ => continue,
+        };
+        term.write_line(&format!(
+            "{} {} {}",
+            style("\u{2714}").green(),
+            style("Due date:").bold(),
+            text
+        ))
+        .with_context(|| "Error writing line!")?;
+        return Ok(text.trim().to_lowercase());
+    }
+}
+
+// Writes the preview and the input line of the due date prompt, then moves the cursor in the input
+// Returns the number of rows written, which depends on the wrapping, and the row of the cursor
+fn write_due_date_prompt(
+    term: &Term,
+    prompt: &str,
+    input: &[char],
+    cursor: usize,
+    preview: &StyledObject<String>,
+) -> Result<(usize, usize)> {
+    let width = (term.size().1 as usize).max(1);
+    let preview = preview.to_string();
+    let line = format!("{}{}", prompt, input.iter().collect::<String>());
+    term.write_line(&preview)
+        .with_context(|| "Error writing line!")?;
+    term.write_str(&line)
+        .with_context(|| "Error writing line!")?;
+    let preview_rows = count_rows(&preview, width);
+    let line_rows = count_rows(&line, width);
+    let before_cursor = measure_text_width(prompt)
+        + measure_text_width(&input[..cursor].iter().collect::<String>());
+    let cursor_row = (before_cursor / width).min(line_rows - 1);
+    term.move_cursor_up(line_rows - 1 - cursor_row)
+        .with_context(|| "Error moving cursor!")?;
+    term.write_str("\r")
+        .with_context(|| "Error moving cursor!")?;
+    term.move_cursor_right(before_cursor - cursor_row * width)
+        .with_context(|| "Error moving cursor!")?;
+    Ok((preview_rows + line_rows, preview_rows + cursor_row))
+}
+
+// Due date as typed in the prompt, a recurring TODO is written with its recurrence rule
+fn get_due_prewrite(
+    prewrite: &Option<MyDate>,
+    prewrite_time: &Option<MyTime>,
+    recurrence: &Option<Recurrence>,
+) -> String {
+    let input = match (prewrite, recurrence) {
+        (_, Some(rule)) => rule.to_string(),
+        (Some(MyDate(date)), None) => format_date(date),
+        (None, None) => "".to_string(),
     };
-    let input = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt("Due date [dd-mm(-YYYY)]: ")
+    match (input.is_empty(), prewrite_time) {
+        (false, Some(MyTime(time))) => format!("{} {}", input, time.format(FORMAT_TIME)),
+        _ => input,
+    }
+}
+
+// Resolved date shown below the due date prompt
+fn get_due_date_preview(input: &str) -> StyledObject<String> {
+    if input.trim().is_empty() {
+        return style("No due date".to_string()).dim();
+    }
+    match convert_str_due(input) {
+        Ok((date, time)) => {
+            let time = time
+                .map(|time| format!(" {}", time.format(FORMAT_TIME)))
+                .unwrap_or_default();
+            let rule = split_due_time(input)
+                .ok()
+                .and_then(|(rule, _)| convert_str_recurrence(&rule).ok())
+                .map(|rule| format!(", then {}", rule))
+                .unwrap_or_default();
+            style(format!(
+                "\u{2192} {}{}{}",
+                date.format(&format!("%A {}", DateFormat::get().get_pattern())),
+                time,
+                rule
+            ))
+            .green()
+        }
+        Err(_) => style("Invalid date".to_string()).red(),
+    }
+}
+
+// Prompts user for the start date, before which the TODO is hidden
+pub fn input_scheduled_date(prewrite: &Option<MyDate>) -> Result<String> {
+    let prewrite_str = prewrite
+        .as_ref()
+        .map_or("".to_string(), |MyDate(date)| format_date(date));
+    let input: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!(
+            "Start date [{}, tomorrow, in 3 days...]: ",
+            DateFormat::get().get_hint()
+        ))
         .allow_empty(true)
         .with_initial_text(prewrite_str)
-        .validate_with(validate_regex)
+        .validate_with(|s: &String| match s.trim().is_empty() {
+            true => Ok(()),
+            false => convert_str_valid_date(s)
+                .map(|_| ())
+                .map_err(|_| "Invalid date!"),
+        })
         .interact_text()
         .with_context(|| "Error reading text!")?;
-    Ok(input.to_lowercase())
+    Ok(input.trim().to_lowercase())
 }
 
 // Prompts user for priority level
@@ -60,40 +235,407 @@ pub fn input_priority(init_position: usize) -> Result<u32> {
     Ok(priorities[selection])
 }
 
-// Parses the title, strikethrough if complete
+// Prompts user for progress level
+pub fn input_progress(init_progress: &Progress) -> Result<Progress> {
+    let levels: Vec<String> = PROGRESS_LEVELS
+        .iter()
+        .map(|level| format!("{}%", level.clone() as u32))
+        .collect();
+    let init_position = PROGRESS_LEVELS
+        .iter()
+        .position(|level| level == init_progress)
+        .unwrap_or(0);
+
+    let selection = Select::new()
+        .with_prompt("Select a progress level")
+        .items(&levels)
+        .default(init_position)
+        .interact()
+        .with_context(|| "Error reading progress levels")?;
+
+    Ok(PROGRESS_LEVELS[selection].clone())
+}
+
+// Prompts user for completion status
+pub fn input_completed(init_completed: bool) -> Result<bool> {
+    let completed = Confirm::new()
+        .with_prompt("Completed?")
+        .default(init_completed)
+        .interact()
+        .with_context(|| "Error reading completion status")?;
+    Ok(completed)
+}
+
+// Prompts user for the tag to filter on, None shows all TODOs
+pub fn input_tag_filter(tags: &[String], current: Option<&str>) -> Result<Option<String>> {
+    let mut items = vec!["(all)".to_string()];
+    items.extend(tags.iter().map(|tag| format!("#{}", tag)));
+    let init_position = current
+        .and_then(|current| tags.iter().position(|tag| tag == current))
+        .map_or(0, |idx| idx + 1);
+
+    let selection = Select::new()
+        .with_prompt("Show TODOs with tag")
+        .items(&items)
+        .default(init_position)
+        .interact()
+        .with_context(|| "Error reading tags")?;
+
+    match selection {
+        0 => Ok(None),
+        idx => Ok(Some(tags[idx - 1].clone())),
+    }
+}
+
+// Prompts user for a filter expression, an empty expression removes the filter
+pub fn input_filter(current: Option<&Filter>) -> Result<Option<Filter>> {
+    let term = Term::stdout();
+    term.write_line(
+        &style("Terms: done, recurring, #tag, tag:work, due<2026-11-01, due:none, priority>=2, progress<50, created>today, text. '!' negates a term")
+            .dim()
+            .to_string(),
+    )
+    .with_context(|| "Error writing line!")?;
+    let expression: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Filter (leave empty to show all): ")
+        .with_initial_text(current.map(|filter| filter.to_string()).unwrap_or_default())
+        .allow_empty(true)
+        .validate_with(|s: &String| -> Result<(), String> {
+            match s.trim().is_empty() {
+                true => Ok(()),
+                false => Filter::parse(s).map(|_| ()).map_err(|e| format!("{:#}", e)),
+            }
+        })
+        .interact_text()
+        .with_context(|| "Error reading input!")?;
+    match expression.trim().is_empty() {
+        true => Ok(None),
+        false => Ok(Some(Filter::parse(&expression)?)),
+    }
+}
+
+// Prompts user for a view among the configured ones, None shows all TODOs
+pub fn input_view(views: &[View], current: Option<&str>) -> Result<Option<usize>> {
+    let mut items = vec!["(none)".to_string()];
+    items.extend(views.iter().map(|view| {
+        let filter = view
+            .get_filter()
+            .as_ref()
+            .map(|filter| filter.to_string())
+            .unwrap_or_default();
+        format!("{}  {}", view.get_name(), style(filter).dim())
+    }));
+    let init_position = current
+        .and_then(|current| views.iter().position(|view| view.get_name() == current))
+        .map_or(0, |idx| idx + 1);
+
+    let selection = Select::new()
+        .with_prompt("Select a view")
+        .items(&items)
+        .default(init_position)
+        .interact()
+        .with_context(|| "Error reading views")?;
+
+    match selection {
+        0 => Ok(None),
+        idx => Ok(Some(idx - 1)),
+    }
+}
+
+// Prompts user for a list among the existing ones or a new list, None goes back
+pub fn input_list(lists: &[String], current: &str) -> Result<Option<String>> {
+    let mut items = lists.to_vec();
+    items.push("(new list)".to_string());
+    let init_position = lists.iter().position(|list| list == current).unwrap_or(0);
+
+    let selection = Select::new()
+        .with_prompt("Select a list")
+        .items(&items)
+        .default(init_position)
+        .interact_opt()
+        .with_context(|| "Error reading lists")?;
+
+    match selection {
+        None => Ok(None),
+        Some(idx) if idx < lists.len() => Ok(Some(lists[idx].clone())),
+        Some(_) => {
+            let name: String = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt("New list name (leave empty to go back): ")
+                .allow_empty(true)
+                .validate_with(|s: &String| -> Result<(), String> {
+                    match s.is_empty() {
+                        true => Ok(()),
+                        false => validate_list_name(s).map_err(|e| e.to_string()),
+                    }
+                })
+                .interact_text()
+                .with_context(|| "Error reading input!")?;
+            match name.is_empty() {
+                true => Ok(None),
+                false => Ok(Some(name)),
+            }
+        }
+    }
+}
+
+// Checklist editor: toggles, adds and removes the checklist items of a TODO
+// Returns whether the checklist was modified
+pub fn edit_checklist(todo: &mut Todo) -> Result<bool> {
+    let mut modified = false;
+    let mut position = 0;
+    loop {
+        clear_term()?;
+        let mut items: Vec<String> = todo.get_subtasks().iter().map(format_subtask).collect();
+        let n_subtasks = items.len();
+        items.push("+ add item".to_string());
+        if n_subtasks > 0 {
+            items.push("- remove item".to_string());
+        }
+        items.push("done".to_string());
+
+        let selection = Select::new()
+            .with_prompt(format!("Checklist of '{}'", todo.get_title()))
+            .items(&items)
+            .default(position.min(items.len() - 1))
+            .interact_opt()
+            .with_context(|| "Error reading checklist")?;
+        match selection {
+            Some(idx) if idx < n_subtasks => {
+                todo.toggle_subtask(idx);
+                position = idx;
+                modified = true;
+            }
+            Some(idx) if idx == n_subtasks => {
+                let title: String = Input::with_theme(&ColorfulTheme::default())
+                    .with_prompt("New item (leave empty to go back): ")
+                    .allow_empty(true)
+                    .interact_text()
+                    .with_context(|| "Error reading input!")?;
+                if !title.trim().is_empty() {
+                    todo.add_subtask(title.trim());
+                    modified = true;
+                }
+                position = n_subtasks + 1;
+            }
+            Some(idx) if n_subtasks > 0 && idx == n_subtasks + 1 => {
+                let remove = Select::new()
+                    .with_prompt("Remove item")
+                    .items(&items[..n_subtasks])
+                    .default(0)
+                    .interact_opt()
+                    .with_context(|| "Error reading checklist")?;
+                if let Some(remove_idx) = remove {
+                    todo.remove_subtask(remove_idx);
+                    modified = true;
+                }
+                position = 0;
+            }
+            _ => return Ok(modified),
+        }
+    }
+}
+
+// Prompts user for the notes of a TODO, in $VISUAL/$EDITOR if set, otherwise line by line
+pub fn input_notes(prewrite: &str) -> Result<String> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_default();
+    if !editor.trim().is_empty() {
+        if let Ok(notes) = input_notes_editor(&editor, prewrite) {
+            return Ok(notes);
+        }
+    }
+    input_notes_prompt(prewrite)
+}
+
+// Opens the notes in an external editor through a temporary file
+// The file is created with a random name only readable by the user, and removed when dropped
+fn input_notes_editor(editor: &str, prewrite: &str) -> Result<String> {
+    let file = tempfile::Builder::new()
+        .prefix("rustdo_notes_")
+        .suffix(".md")
+        .tempfile()
+        .with_context(|| "Error creating temporary file!")?;
+    let path = file.path();
+    fs::write(path, prewrite).with_context(|| "Error writing temporary file!")?;
+    let mut command_parts = editor.split_whitespace();
+    let program = command_parts.next().unwrap_or_default();
+    let status = Command::new(program)
+        .args(command_parts)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Error launching editor {}!", editor));
+    let notes = fs::read_to_string(path).with_context(|| "Error reading temporary file!");
+    if !status?.success() {
+        bail!("Editor exited with an error!");
+    }
+    Ok(notes?.trim_end().to_string())
+}
+
+// Reads the notes line by line, an empty line finishes
+fn input_notes_prompt(prewrite: &str) -> Result<String> {
+    let term = Term::stdout();
+    if !prewrite.is_empty() {
+        term.write_line(&format!("Current notes:\n{}\n", prewrite))
+            .with_context(|| "Error writing line!")?;
+    }
+    term.write_line("Type the notes line by line, an empty line finishes (empty notes keep the current ones, '-' clears them)")
+        .with_context(|| "Error writing line!")?;
+    let mut lines: Vec<String> = Vec::new();
+    loop {
+        let line: String = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt(">")
+            .allow_empty(true)
+            .interact_text()
+            .with_context(|| "Error reading input!")?;
+        if line.is_empty() {
+            break;
+        }
+        lines.push(line);
+    }
+    match lines.as_slice() {
+        [] => Ok(prewrite.to_string()),
+        [line] if line == "-" => Ok(String::new()),
+        _ => Ok(lines.join("\n")),
+    }
+}
+
+// Prints every field of a TODO, with its checklist and notes
+pub fn write_todo_details(todo: &Todo) -> Result<()> {
+    let term = Term::stdout();
+    for line in get_todo_details(todo) {
+        term.write_line(&line)
+            .with_context(|| "Error writing line!")?;
+    }
+    Ok(())
+}
+
+// Lines of the details of a TODO, also shown in the detail pane of the full-screen mode
+fn get_todo_details(todo: &Todo) -> Vec<String> {
+    let due = match (get_due_date(todo), todo.get_recurrence()) {
+        (Some(date_str), Some(rule)) => format!("{} ({})", date_str, rule),
+        (Some(date_str), None) => date_str.to_string(),
+        (None, _) => "never".to_string(),
+    };
+    let mut lines = vec![
+        style(todo.get_title()).bold().to_string(),
+        format!("Id:        {}", todo.get_id()),
+        format!("Tags:      {}", format_tags(todo.get_tags())),
+        format!("Priority:  {}", get_priority_symbol(todo.get_priority())),
+        format!("Due:       {}", due),
+        format!(
+            "Scheduled: {}",
+            todo.get_scheduled_date()
+                .as_ref()
+                .map_or("-".to_string(), |MyDate(date)| format_date(date))
+        ),
+        format!(
+            "Created:   {}",
+            format_datetime(&todo.get_created_date().get_0())
+        ),
+        format!("Progress:  {}", get_progress_str(todo)),
+        format!(
+            "Completed: {}",
+            match (todo.is_complete(), todo.get_completed_date()) {
+                (true, Some(date)) => format_datetime(&date.get_0()),
+                (true, None) => "yes".to_string(),
+                (false, _) => "no".to_string(),
+            }
+        ),
+    ];
+    if !todo.get_subtasks().is_empty() {
+        lines.push("Checklist:".to_string());
+        lines.extend(
+            todo.get_subtasks()
+                .iter()
+                .map(|subtask| format!("  {}", format_subtask(subtask))),
+        );
+    }
+    if !todo.get_notes().is_empty() {
+        lines.push("Notes:".to_string());
+        lines.extend(todo.get_notes().lines().map(|line| format!("  {}", line)));
+    }
+    lines
+}
+
+// Parses the title, with the completed style of the theme (strikethrough by default) if complete
 pub fn get_title_complete(todo: &Todo) -> StyledObject<&str> {
-    let style_base = style(todo.get_title());
     match todo.is_complete() {
-        true => style_base.strikethrough(),
-        false => style_base,
+        true => get_theme()
+            .get_completed_title_style()
+            .apply_to(todo.get_title()),
+        false => style(todo.get_title()),
     }
 }
 
-// Parses due date with the appropriate color depending on today's date
-// Green -- future due date, Orange -- today is the due date, Red -- passed due date
+// Parses due date with the style of the theme depending on today's date
+// By default green -- future due date, orange -- today is the due date, red -- passed due date
+// With a due time, today's style within DUE_WARNING_MINUTES of the due time and overdue once it is passed
 // Dimmed gray color for completed TODOs
 pub fn get_due_date(todo: &Todo) -> Option<StyledObject<String>> {
-    let today = Local::now().date_naive();
-    todo.get_due_date().as_ref().map_or_else(
-        || None,
-        |due| {
-            let base = style(due.get_0().format(FORMAT_DATE).to_string());
-            if todo.is_complete() {
-                return Some(base.dim());
-            }
-            match due.get_0().cmp(&today) {
-                Ordering::Greater => Some(base.green()),
-                Ordering::Equal => Some(base.yellow()),
-                Ordering::Less => Some(base.red()),
-            }
-        },
-    )
+    todo.get_due_date().as_ref().map(|due| {
+        let date_str = match todo.get_due_time() {
+            Some(MyTime(time)) => format!(
+                "{} {}",
+                format_due_date(&due.get_0()),
+                time.format(FORMAT_TIME)
+            ),
+            None => format_due_date(&due.get_0()),
+        };
+        get_theme()
+            .get_due_style(get_due_status(todo), todo.is_complete())
+            .apply_to(date_str)
+    })
+}
+
+// Compares the due date with the current time, None without due date
+// Soon on the day it is due, or within DUE_WARNING_MINUTES of the due time
+pub fn get_due_status(todo: &Todo) -> Option<DueStatus> {
+    let now = Local::now().naive_local();
+    let due = todo.get_due_datetime()?;
+    if todo.get_due_time().is_some() {
+        return match due - now {
+            left if left < chrono::Duration::zero() => Some(DueStatus::Overdue),
+            left if left <= get_due_warning_window() => Some(DueStatus::Soon),
+            _ => Some(DueStatus::Upcoming),
+        };
+    }
+    match due.date().cmp(&now.date()) {
+        Ordering::Greater => Some(DueStatus::Upcoming),
+        Ordering::Equal => Some(DueStatus::Soon),
+        Ordering::Less => Some(DueStatus::Overdue),
+    }
+}
+
+// Whether the interactive screen starts in the full-screen mode, off by default
+pub fn is_full_screen_configured() -> bool {
+    env::var("FULL_SCREEN")
+        .ok()
+        .and_then(|full_screen| full_screen.parse::<bool>().ok())
+        .unwrap_or(false)
+}
+
+// Time before a due time during which the due date is highlighted, one hour by default
+fn get_due_warning_window() -> chrono::Duration {
+    let minutes = env::var("DUE_WARNING_MINUTES")
+        .ok()
+        .and_then(|minutes| minutes.parse::<i64>().ok())
+        .unwrap_or(60);
+    chrono::Duration::minutes(minutes)
 }
 
 // Converts progress status to string progress bar
+// TODOs with a checklist show the fraction of completed items instead
 pub fn get_progress_str(todo: &Todo) -> String {
     let ret_str = if todo.is_complete() {
         "[########]"
+    } else if let Some(ratio) = todo.get_subtasks_ratio() {
+        let filled = (ratio * PROGRESS_WIDTH as f64).round() as usize;
+        return format!(
+            "[{}{}]",
+            "#".repeat(filled),
+            " ".repeat(PROGRESS_WIDTH - filled)
+        );
     } else {
         match todo.get_progress() {
             Progress::Zero => "[        ]",
@@ -114,44 +656,141 @@ pub fn get_priority_symbol(p: u32) -> String {
     }
 }
 
+// Given a TODO element, formats the TODO row with the ROW_TEMPLATE of the theme
+// By default the priority, title, tags, notes marker, start date, due date and progress
+pub fn format_todo(todo: &Todo) -> String {
+    let theme = get_theme();
+    theme.render_row(|field| match field {
+        RowField::Priority => theme
+            .get_priority_style(todo.get_priority())
+            .apply_to(get_priority_symbol(todo.get_priority()))
+            .to_string(),
+        RowField::Title => get_title_complete(todo).to_string(),
+        RowField::Tags => match todo.get_tags().is_empty() {
+            true => String::new(),
+            false => theme
+                .get_tags_style()
+                .apply_to(format_tags(todo.get_tags()))
+                .to_string(),
+        },
+        // Pencil marker for TODOs with notes
+        RowField::Notes => match todo.get_notes().is_empty() {
+            true => String::new(),
+            false => style("\u{270E}").dim().to_string(),
+        },
+        // Start date of the TODOs which cannot be started yet
+        RowField::Start => match (todo.is_scheduled_later(), todo.get_scheduled_date()) {
+            (true, Some(MyDate(date))) => style(format!("(starts {})", format_due_date(date)))
+                .dim()
+                .to_string(),
+            _ => String::new(),
+        },
+        RowField::Due => match (get_due_date(todo), todo.get_recurrence()) {
+            (Some(date_str), Some(rule)) => format!("{} ({})", date_str, style(rule).dim()),
+            (Some(date_str), None) => date_str.to_string(),
+            (None, _) => String::new(),
+        },
+        RowField::Progress => get_progress_str(todo),
+    })
+}
+
+// Marks the selected row, highlighted with the selection style of the theme if it has one
+fn format_row(row: &str, is_position: bool) -> String {
+    match (is_position, get_theme().get_selection_style()) {
+        (true, Some(selection)) => selection
+            .apply_to(format!("> {}", strip_ansi_codes(row)))
+            .to_string(),
+        (true, None) => format!("> {}", row),
+        (false, _) => format!("  {}", row),
+    }
+}
+
 // Given a TODO element, prints the TODO onscreen
 pub fn write_todo(todo: &Todo, is_position: bool) -> Result<()> {
     let term = Term::stdout();
-    let title = get_title_complete(todo);
-    let date = get_due_date(todo);
-    let priority = get_priority_symbol(todo.get_priority());
-    let initial_character = match is_position {
-        true => ">",
-        false => " ",
-    };
-    let progress_bar = get_progress_str(todo);
-    let str_write = match date {
-        Some(date_str) => format!(
-            "{} {} {} - Due: {} - Progress: {}",
-            initial_character, priority, title, date_str, progress_bar
-        ),
-        None => format!(
-            "{} {} {} - Progress: {}",
-            initial_character, priority, title, progress_bar
-        ),
-    };
-    term.write_line(&str_write)
+    for line in get_todo_lines(todo, is_position) {
+        term.write_line(&line)
+            .with_context(|| "Error while writing line!")?;
+    }
+    Ok(())
+}
+
+// Lines of a TODO row and its checklist, the selected TODO is marked with '>'
+fn get_todo_lines(todo: &Todo, is_position: bool) -> Vec<String> {
+    let mut lines = vec![format_row(&format_todo(todo), is_position)];
+    lines.extend(
+        todo.get_subtasks()
+            .iter()
+            .map(|subtask| format!("    {}", format_subtask(subtask))),
+    );
+    lines
+}
+
+// Formats a checklist item, dimmed and strikethrough if complete
+pub fn format_subtask(subtask: &Subtask) -> String {
+    match subtask.is_complete() {
+        true => style(format!("[x] {}", subtask.get_title()))
+            .dim()
+            .strikethrough()
+            .to_string(),
+        false => format!("[ ] {}", subtask.get_title()),
+    }
+}
+
+// Prints the checklist of a TODO, indented under the TODO
+fn write_subtasks(todo: &Todo, indent: usize) -> Result<()> {
+    let term = Term::stdout();
+    for subtask in todo.get_subtasks() {
+        term.write_line(&format!(
+            "{}{}",
+            " ".repeat(indent),
+            format_subtask(subtask)
+        ))
         .with_context(|| "Error while writing line!")?;
+    }
     Ok(())
 }
 
-// Clears terminal
-pub fn clear_term() -> Result<()> {
+// Prints a list of TODOs prefixed by the shortest unambiguous prefix of their ids
+pub fn write_todo_list(todos: &[Todo]) -> Result<()> {
     let term = Term::stdout();
-    term.clear_screen()
-        .with_context(|| "Error clearing screen!")?;
+    let ids: Vec<&str> = todos.iter().map(|todo| todo.get_id()).collect();
+    let prefix_len = get_unique_prefix_len(&ids);
+    for todo in todos {
+        let id = todo.get_id();
+        let str_write = format!(
+            "{} {}",
+            style(get_id_prefix(id, prefix_len)).dim(),
+            format_todo(todo)
+        );
+        term.write_line(&str_write)
+            .with_context(|| "Error while writing line!")?;
+        write_subtasks(todo, prefix_len + 3)?;
+    }
     Ok(())
 }
 
-// Clears last n lines on terminal
-pub fn clear_menu() -> Result<()> {
+// First n characters of an id, ids imported from markdown may contain any character
+fn get_id_prefix(id: &str, n: usize) -> &str {
+    id.char_indices().nth(n).map_or(id, |(idx, _)| &id[..idx])
+}
+
+// Shortest prefix length (at least MINPREFIX) telling apart all the given ids
+fn get_unique_prefix_len(ids: &[&str]) -> usize {
+    let max_len = ids.iter().map(|id| id.chars().count()).max().unwrap_or(0);
+    (MINPREFIX..max_len)
+        .find(|&n| {
+            let mut prefixes: Vec<&str> = ids.iter().map(|id| get_id_prefix(id, n)).collect();
+            prefixes.sort_unstable();
+            prefixes.windows(2).all(|w| w[0] != w[1])
+        })
+        .unwrap_or(max_len)
+}
+
+// Clears terminal
+pub fn clear_term() -> Result<()> {
     let term = Term::stdout();
-    term.clear_last_lines(NERASE)
+    term.clear_screen()
         .with_context(|| "Error clearing screen!")?;
     Ok(())
 }
@@ -172,24 +811,22 @@ pub fn hide_cursor() -> Result<()> {
     Ok(())
 }
 
-// This function prompts the user for input on sorthing methods
-pub fn wait_sort_key() -> Result<Option<SortingMethod>> {
+// This function prompts the user for input on sorting methods, with the keys of the sort menu of the keymap
+pub fn wait_sort_key(keymap: &Keymap) -> Result<Option<Action>> {
     let term = Term::stdout();
-    term.write_line(
-        "p: sort by priority   c: sort by date of creation\t
-d: sort by due date   Backspace: go back
-        ",
-    )
-    .with_context(|| "Error writing line!")?;
-    loop {
-        let key = term.read_key().with_context(|| "Error reading key!")?;
-        match key {
-            Key::Backspace => return Ok(None),
-            Key::Char('p') => return Ok(Some(SortingMethod::Priority)),
-            Key::Char('d') => return Ok(Some(SortingMethod::Due)),
-            Key::Char('c') => return Ok(Some(SortingMethod::Created)),
-            _ => continue,
-        }
+    term.write_line(&keymap.get_sort_menu(term.size().1 as usize))
+        .with_context(|| "Error writing line!")?;
+    read_sort_key(keymap)
+}
+
+// Reads the keys of the sort menu until a sorting action is chosen, None to go back
+fn read_sort_key(keymap: &Keymap) -> Result<Option<Action>> {
+    match read_bound_event(keymap.get_sort_bindings())? {
+        SortEvent::By(sort_key) => Ok(Some(Action::Sort(SortingMethod::by_key(sort_key)))),
+        SortEvent::ThenBy(sort_key) => Ok(Some(Action::ThenSort(sort_key))),
+        SortEvent::Reverse => Ok(Some(Action::ReverseSort)),
+        SortEvent::ToggleCompletedLast => Ok(Some(Action::ToggleCompletedLast)),
+        SortEvent::Back => Ok(None),
     }
 }
 
@@ -198,6 +835,12 @@ pub fn wait_confirm(message: &str) -> Result<bool> {
     let term = Term::stdout();
     term.write_line(format!("{} [y/N]", message).as_str())
         .with_context(|| "Error writing line!")?;
+    read_confirm_key()
+}
+
+// Reads keys until the prompt is answered, only 'y' confirms
+fn read_confirm_key() -> Result<bool> {
+    let term = Term::stdout();
     loop {
         let key = term.read_key().with_context(|| "Error reading key!")?;
         match key {
@@ -208,100 +851,904 @@ pub fn wait_confirm(message: &str) -> Result<bool> {
     }
 }
 
-fn confirm_message(message: &str) -> Result<()> {
+// Waits for the user to press any key
+pub fn wait_any_key() -> Result<()> {
     let term = Term::stdout();
-    term.write_line(message)?;
+    term.write_line("Press any key to continue...")
+        .with_context(|| "Error writing line!")?;
+    term.read_key().with_context(|| "Error reading key!")?;
     Ok(())
 }
 
-// This function prompts the user for input on actions to take
-pub fn wait_key_event() -> Result<KeyEvent> {
+// Summarizes a markdown import, listing the lines which could not be parsed
+pub fn write_import_report(count: usize, errors: &[ImportError]) -> Result<()> {
     let term = Term::stdout();
-    term.write_line(MENU)
+    term.write_line(&format!("Imported {} TODOs", count))
         .with_context(|| "Error writing line!")?;
+    for (line_number, error) in errors {
+        term.write_line(&format!(
+            "{} line {}: {}",
+            style("Skipped").red(),
+            line_number,
+            error
+        ))
+        .with_context(|| "Error writing line!")?;
+    }
+    Ok(())
+}
+
+// Writes a simple message on the terminal
+pub fn confirm_message(message: &str) -> Result<()> {
+    let term = Term::stdout();
+    term.write_line(message)?;
+    Ok(())
+}
+
+// Prints a message and waits for it to be visible before the screen is redrawn
+pub fn flash_message(message: &str) -> Result<()> {
+    confirm_message(message)?;
+    std::thread::sleep(std::time::Duration::from_millis(400));
+    Ok(())
+}
+
+// This function waits for a key bound to an action, the menu is drawn with the list
+pub fn wait_key_event(keymap: &Keymap) -> Result<KeyEvent> {
+    read_bound_event(keymap.get_list_bindings())
+}
+
+// Reads keys until they form a bound sequence, e.g. "dd"
+// A key which does not continue the sequence starts a new one, unbound keys are ignored
+fn read_bound_event<E: Copy + PartialEq>(bindings: &Bindings<E>) -> Result<E> {
+    let term = Term::stdout();
+    let mut keys = Vec::new();
     loop {
-        let key = term.read_key().with_context(|| "Error reading key!")?;
-        match key {
-            Key::Enter => return Ok(KeyEvent::Back),
-            Key::Char('m') => return Ok(KeyEvent::Export),
-            Key::Char('a') => return Ok(KeyEvent::Add),
-            Key::Char('e') => return Ok(KeyEvent::Edit),
-            Key::Char('s') => return Ok(KeyEvent::Sort),
-            Key::Char('x') => return Ok(KeyEvent::ToggleRead),
-            Key::Char('z') => return Ok(KeyEvent::Delete),
-            Key::Char('+') => return Ok(KeyEvent::IncreasePriority),
-            Key::Char('-') => return Ok(KeyEvent::DecreasePriority),
-            Key::Char('Z') => return Ok(KeyEvent::DeleteCompleted),
-            Key::ArrowLeft => return Ok(KeyEvent::DecreaseProgress),
-            Key::ArrowRight => return Ok(KeyEvent::IncreaseProgress),
-            Key::ArrowUp => return Ok(KeyEvent::NavigateUp),
-            Key::ArrowDown => return Ok(KeyEvent::NavigateDown),
-            _ => continue,
+        keys.push(term.read_key().with_context(|| "Error reading key!")?);
+        loop {
+            match bindings.lookup(&keys) {
+                Lookup::Event(event) => return Ok(event),
+                Lookup::Prefix => break,
+                Lookup::Unbound if keys.len() > 1 => {
+                    keys.remove(0);
+                }
+                Lookup::Unbound => {
+                    keys.clear();
+                    break;
+                }
+            }
         }
     }
 }
 
-// Main printing function, prints TODOs, menu and handles simple actions, otherwise returns to service
-pub fn screen_navigate_todos(
-    todos: &mut Vec<Todo>,
-    position: usize,
-) -> Result<Option<(usize, Action)>> {
-    clear_term()?;
-    hide_cursor()?;
-    let size_todos = todos.len();
-    let pos_fixed = get_pos_overflow(position, size_todos);
-    for (idx, todo) in todos.iter().enumerate() {
-        write_todo(todo, idx == pos_fixed)?
-    }
-    let key_event = wait_key_event()?;
-    match key_event {
-        KeyEvent::Back => {
-            show_cursor()?;
-            Ok(None)
+// Indices of the TODOs matching the search query, all TODOs if the query is empty
+fn get_visible_todos(todos: &[Todo], search: &str) -> Vec<usize> {
+    (0..todos.len())
+        .filter(|&idx| todos[idx].matches_text(search))
+        .collect()
+}
+
+// Number of terminal rows taken by a text once its lines are wrapped, an empty line still takes a row
+fn count_rows(text: &str, width: usize) -> usize {
+    text.split('\n')
+        .map(|line| measure_text_width(line).div_ceil(width.max(1)).max(1))
+        .sum()
+}
+
+// Range of the visible TODOs fitting in the given number of rows
+// The scroll offset (first row shown) only moves to keep the selected row in view
+fn get_viewport(heights: &[usize], row: usize, scroll: &mut usize, rows: usize) -> (usize, usize) {
+    let count = heights.len();
+    let row = row.min(count.saturating_sub(1));
+    *scroll = (*scroll).min(row);
+    while *scroll < row && heights[*scroll..=row].iter().sum::<usize>() > rows {
+        *scroll += 1;
+    }
+    let mut end = *scroll;
+    let mut used = 0;
+    while end < count && (end == *scroll || used + heights[end] <= rows) {
+        used += heights[end];
+        end += 1;
+    }
+    // Space left at the bottom, e.g. after the terminal grew, is filled with the previous rows
+    while end == count && *scroll > 0 && used + heights[*scroll - 1] <= rows {
+        *scroll -= 1;
+        used += heights[*scroll];
+    }
+    (*scroll, end)
+}
+
+// Lines of the last frame drawn on the terminal, only the lines which changed are redrawn
+// The frame is drawn again entirely after the terminal was resized or something else was written
+#[derive(Default)]
+pub struct Screen {
+    lines: Vec<String>,
+    size: (u16, u16),
+    scroll: usize,
+    full_screen: bool,
+    panes: Panes,
+    // Rows taken by the footer (menu) at the bottom of the frame
+    footer_rows: usize,
+}
+impl Screen {
+    // Full-screen mode, drawn in the alternate screen buffer until the screen is dropped
+    pub fn full_screen() -> Result<Screen> {
+        Term::stdout()
+            .write_str("\x1b[?1049h")
+            .with_context(|| "Error switching to the alternate screen!")?;
+        Ok(Screen {
+            lines: Vec::new(),
+            size: (0, 0),
+            scroll: 0,
+            full_screen: true,
+            panes: Panes::default(),
+            footer_rows: 0,
+        })
+    }
+    pub fn is_full_screen(&self) -> bool {
+        self.full_screen
+    }
+    // Forces a full redraw, e.g. after a prompt was written over the frame
+    pub fn invalidate(&mut self) {
+        self.lines.clear()
+    }
+    // Clears the footer of the last frame, so that a prompt can be written in its place
+    pub fn clear_menu(&mut self) -> Result<()> {
+        Term::stdout()
+            .clear_last_lines(self.footer_rows)
+            .with_context(|| "Error clearing screen!")?;
+        self.invalidate();
+        Ok(())
+    }
+    // Asks for a confirmation, in the detail pane in full-screen mode and below the list otherwise
+    pub fn confirm(&mut self, message: &str) -> Result<bool> {
+        if !self.full_screen {
+            self.clear_menu()?;
+            return wait_confirm(message);
         }
-        KeyEvent::Sort => {
-            clear_menu()?;
-            let sorting = wait_sort_key()?;
-            match sorting {
-                Some(sort) => Ok(Some((0, Action::Sort(sort)))),
-                None => Ok(Some((pos_fixed, Action::Reload))),
+        draw_panes(self, vec![style(message).bold().to_string()], CONFIRM_MENU)?;
+        read_confirm_key()
+    }
+    // Shows the sort menu, in the detail pane in full-screen mode and below the list otherwise
+    pub fn wait_sort_key(&mut self, keymap: &Keymap) -> Result<Option<Action>> {
+        if !self.full_screen {
+            self.clear_menu()?;
+            return wait_sort_key(keymap);
+        }
+        let (_, _, detail_width) = get_panes_layout(SORT_PROMPT);
+        let mut details = vec![style("Sort by").bold().to_string(), String::new()];
+        details.extend(
+            keymap
+                .get_sort_menu(detail_width)
+                .lines()
+                .map(|line| line.to_string()),
+        );
+        draw_panes(self, details, SORT_PROMPT)?;
+        read_sort_key(keymap)
+    }
+    // Draws a frame, the lines must not contain line breaks
+    fn draw(&mut self, lines: Vec<String>) -> Result<()> {
+        let term = Term::stdout();
+        let size = term.size();
+        let width = size.1 as usize;
+        let rows: Vec<usize> = lines.iter().map(|line| count_rows(line, width)).collect();
+        // Rows are addressed from the top of the terminal, which only works if nothing wrapped differently
+        // and the frame did not scroll the terminal
+        let redraw_all = size != self.size
+            || lines.len() != self.lines.len()
+            || rows.iter().sum::<usize>() >= size.0 as usize
+            || lines
+                .iter()
+                .zip(&self.lines)
+                .zip(&rows)
+                .any(|((new, old), &n)| new != old && count_rows(old, width) != n);
+        if redraw_all {
+            clear_term()?;
+            for line in &lines {
+                term.write_line(line)
+                    .with_context(|| "Error while writing line!")?;
+            }
+        } else {
+            let mut y = 0;
+            for ((new, old), &n) in lines.iter().zip(&self.lines).zip(&rows) {
+                if new != old {
+                    for row in y..y + n {
+                        term.move_cursor_to(0, row)
+                            .with_context(|| "Error moving cursor!")?;
+                        term.clear_line().with_context(|| "Error clearing line!")?;
+                    }
+                    term.move_cursor_to(0, y)
+                        .with_context(|| "Error moving cursor!")?;
+                    term.write_str(new)
+                        .with_context(|| "Error while writing line!")?;
+                }
+                y += n;
             }
+            term.move_cursor_to(0, y)
+                .with_context(|| "Error moving cursor!")?;
         }
-        KeyEvent::Delete => {
-            clear_menu()?;
-            let confirmation = wait_confirm("Confirm deletion?")?;
-            match confirmation {
-                true => Ok(Some((pos_fixed, Action::Delete))),
-                false => Ok(Some((pos_fixed, Action::Reload))),
+        self.lines = lines;
+        self.size = size;
+        Ok(())
+    }
+}
+impl Drop for Screen {
+    // Leaves the alternate screen buffer, the terminal is restored as it was before the full-screen mode
+    fn drop(&mut self) {
+        if self.full_screen {
+            Term::stdout().write_str("\x1b[?1049l").ok();
+        }
+    }
+}
+
+// Draws the header, the active search and the visible TODOs with the selection marker, then the footer (menu)
+// Only the TODOs fitting in the terminal above the footer are drawn, scrolling with the selection
+// The terminal size is read on each redraw, so that the list follows the resizes
+// Returns the number of TODOs drawn, i.e. the size of a page
+fn draw_todos_screen(
+    screen: &mut Screen,
+    todos: &[Todo],
+    visible: &[usize],
+    selected: Option<usize>,
+    header: Option<&str>,
+    search: &str,
+    footer: &str,
+) -> Result<usize> {
+    if screen.full_screen {
+        return draw_panes_screen(screen, todos, visible, selected, header, search, footer);
+    }
+    let (height, width) = Term::stdout().size();
+    let (height, width) = (height as usize, width as usize);
+    let mut lines = Vec::new();
+    if let Some(header) = header {
+        lines.push(style(header).bold().to_string());
+    }
+    if !search.is_empty() {
+        lines.push(format!(
+            "{} ({} of {})",
+            style(format!("Search: /{}", search)).bold(),
+            visible.len(),
+            todos.len()
+        ));
+    }
+    // A row is left for the cursor below the footer
+    let used: usize = lines.iter().map(|line| count_rows(line, width)).sum();
+    let mut rows = height.saturating_sub(used + count_rows(footer, width) + 1);
+    let todo_lines: Vec<Vec<String>> = visible
+        .iter()
+        .map(|&idx| get_todo_lines(&todos[idx], Some(idx) == selected))
+        .collect();
+    let heights: Vec<usize> = todo_lines
+        .iter()
+        .map(|todo| todo.iter().map(|line| count_rows(line, width)).sum())
+        .collect();
+    // A row is kept for the position indicator when the list does not fit
+    if heights.iter().sum::<usize>() > rows {
+        rows = rows.saturating_sub(1);
+    }
+    let row = selected
+        .and_then(|idx| visible.iter().position(|&v| v == idx))
+        .unwrap_or(0);
+    let (start, end) = get_viewport(&heights, row, &mut screen.scroll, rows);
+    lines.extend(todo_lines[start..end].iter().flatten().cloned());
+    if end - start < visible.len() {
+        lines.push(
+            style(format!(
+                "showing {}-{} of {}",
+                start + 1,
+                end,
+                visible.len()
+            ))
+            .dim()
+            .to_string(),
+        );
+    }
+    lines.extend(footer.lines().map(|line| line.to_string()));
+    screen.footer_rows = footer.lines().map(|line| count_rows(line, width)).sum();
+    screen.draw(lines)?;
+    Ok(end - start)
+}
+
+// Parts of the last full-screen frame kept while the detail pane shows the edit form
+#[derive(Default)]
+struct Panes {
+    top: String,
+    list: Vec<String>,
+    status: String,
+}
+
+// Number of rows of the panes, width of the list pane and of the detail pane
+// The top line, the status bar, the footer and a row for the cursor are drawn around the panes
+fn get_panes_layout(footer: &str) -> (usize, usize, usize) {
+    let (height, width) = Term::stdout().size();
+    let (height, width) = (height as usize, width as usize);
+    let rows = height.saturating_sub(footer.lines().count() + 3);
+    let list_width = width.saturating_sub(3) * 3 / 5;
+    (rows, list_width, width.saturating_sub(list_width + 3))
+}
+
+// Full-screen mode: the list pane on the left, the details of the selected TODO on the right,
+// the header and the active search above and a status bar with counts below
+// Returns the number of TODOs drawn, i.e. the size of a page
+fn draw_panes_screen(
+    screen: &mut Screen,
+    todos: &[Todo],
+    visible: &[usize],
+    selected: Option<usize>,
+    header: Option<&str>,
+    search: &str,
+    footer: &str,
+) -> Result<usize> {
+    let (rows, list_width, _) = get_panes_layout(footer);
+    let mut top: Vec<String> = header.iter().map(|header| header.to_string()).collect();
+    if !search.is_empty() {
+        top.push(format!(
+            "Search: /{} ({} of {})",
+            search,
+            visible.len(),
+            todos.len()
+        ));
+    }
+    let row = selected
+        .and_then(|idx| visible.iter().position(|&v| v == idx))
+        .unwrap_or(0);
+    let (start, end) = get_viewport(&vec![1; visible.len()], row, &mut screen.scroll, rows);
+    let list = visible[start..end]
+        .iter()
+        .map(|&idx| {
+            format_row(
+                &truncate_str(
+                    &format_todo(&todos[idx]),
+                    list_width.saturating_sub(2),
+                    "\u{2026}",
+                ),
+                Some(idx) == selected,
+            )
+        })
+        .collect();
+    let mut status = get_status_counts(todos);
+    if end - start < visible.len() {
+        status = format!(
+            "{}   showing {}-{} of {}",
+            status,
+            start + 1,
+            end,
+            visible.len()
+        );
+    }
+    screen.panes = Panes {
+        top: top.join(" | "),
+        list,
+        status,
+    };
+    let details = match selected {
+        Some(idx) => get_todo_details(&todos[idx]),
+        None => vec![style("No TODO").dim().to_string()],
+    };
+    draw_panes(screen, details, footer)?;
+    Ok(end - start)
+}
+
+// Counts shown in the status bar of the full-screen mode
+fn get_status_counts(todos: &[Todo]) -> String {
+    let today = Local::now().date_naive();
+    let open = todos.iter().filter(|todo| !todo.is_complete()).count();
+    let overdue = todos
+        .iter()
+        .filter(|todo| {
+            !todo.is_complete() && matches!(get_due_status(todo), Some(DueStatus::Overdue))
+        })
+        .count();
+    let done_today = todos
+        .iter()
+        .filter(|todo| {
+            todo.is_complete()
+                && todo
+                    .get_completed_date()
+                    .as_ref()
+                    .is_some_and(|date| date.get_0().date() == today)
+        })
+        .count();
+    format!(
+        "{} open   {} overdue   {} done today",
+        open, overdue, done_today
+    )
+}
+
+// Draws the last list pane next to the given detail pane, the lines of both panes are cut to their width
+fn draw_panes(screen: &mut Screen, details: Vec<String>, footer: &str) -> Result<()> {
+    let (rows, list_width, detail_width) = get_panes_layout(footer);
+    let width = list_width + detail_width + 3;
+    let details: Vec<String> = details
+        .iter()
+        .flat_map(|line| wrap_pane_line(line, detail_width))
+        .collect();
+    let mut lines = vec![style(truncate_str(&screen.panes.top, width, "\u{2026}"))
+        .bold()
+        .to_string()];
+    for row in 0..rows {
+        lines.push(format!(
+            "{} {} {}",
+            pad_str(
+                screen.panes.list.get(row).map_or("", |line| line.as_str()),
+                list_width,
+                Alignment::Left,
+                Some("\u{2026}"),
+            ),
+            style("\u{2502}").dim(),
+            details.get(row).map_or("", |line| line.as_str())
+        ));
+    }
+    lines.push(
+        style(pad_str(
+            &format!(" {}", screen.panes.status),
+            width,
+            Alignment::Left,
+            Some("\u{2026}"),
+        ))
+        .reverse()
+        .to_string(),
+    );
+    lines.extend(
+        footer
+            .lines()
+            .map(|line| truncate_str(line, width, "\u{2026}").to_string()),
+    );
+    screen.footer_rows = footer.lines().count();
+    screen.draw(lines)
+}
+
+// Wraps a line of the detail pane on words to its width, styled lines and long words are cut instead
+fn wrap_pane_line(line: &str, width: usize) -> Vec<String> {
+    if measure_text_width(line) <= width || strip_ansi_codes(line) != line {
+        return vec![truncate_str(line, width, "\u{2026}").to_string()];
+    }
+    let indent = " ".repeat(line.len() - line.trim_start().len());
+    let mut lines = Vec::new();
+    let mut current = indent.clone();
+    for word in line.split_whitespace() {
+        if current.len() > indent.len() {
+            if measure_text_width(&current) + 1 + measure_text_width(word) > width {
+                lines.push(current);
+                current = indent.clone();
+            } else {
+                current.push(' ');
             }
         }
-        KeyEvent::DeleteCompleted => {
-            clear_menu()?;
-            let confirmation = wait_confirm("Confirm deletions?")?;
-            match confirmation {
-                true => Ok(Some((pos_fixed, Action::DeleteCompleted))),
-                false => Ok(Some((pos_fixed, Action::Reload))),
+        current.push_str(word);
+    }
+    lines.push(current);
+    lines
+        .iter()
+        .map(|line| truncate_str(line, width, "\u{2026}").to_string())
+        .collect()
+}
+
+// Fields of the inline edit form of the full-screen mode
+#[derive(Clone, Copy, PartialEq)]
+enum FormField {
+    Title,
+    Due,
+    Start,
+    Priority,
+}
+const FORM_FIELDS: [FormField; 4] = [
+    FormField::Title,
+    FormField::Due,
+    FormField::Start,
+    FormField::Priority,
+];
+
+// Values typed in the inline edit form, in the same format as in the prompts
+#[derive(Default)]
+pub struct TodoForm {
+    pub title: String,
+    pub due: String,
+    pub start: String,
+    pub priority: String,
+}
+impl TodoForm {
+    // Form prewritten with the fields of an existing TODO
+    pub fn from_todo(todo: &Todo) -> TodoForm {
+        TodoForm {
+            title: match todo.get_tags().is_empty() {
+                true => todo.get_title().to_string(),
+                false => format!("{} {}", todo.get_title(), format_tags(todo.get_tags())),
+            },
+            due: get_due_prewrite(
+                todo.get_due_date(),
+                todo.get_due_time(),
+                todo.get_recurrence(),
+            ),
+            start: todo
+                .get_scheduled_date()
+                .as_ref()
+                .map_or("".to_string(), |MyDate(date)| format_date(date)),
+            priority: todo.get_priority().to_string(),
+        }
+    }
+    pub fn get_priority(&self) -> u32 {
+        self.priority.trim().parse::<u32>().unwrap_or(0)
+    }
+    fn get_value(&mut self, field: FormField) -> &mut String {
+        match field {
+            FormField::Title => &mut self.title,
+            FormField::Due => &mut self.due,
+            FormField::Start => &mut self.start,
+            FormField::Priority => &mut self.priority,
+        }
+    }
+    // Resolved value shown below the field, None when the field is valid without preview
+    fn get_preview(&self, field: FormField) -> Option<StyledObject<String>> {
+        match field {
+            FormField::Title => None,
+            FormField::Due => Some(get_due_date_preview(&self.due)),
+            FormField::Start if self.start.trim().is_empty() => None,
+            FormField::Start => match convert_str_valid_date(&self.start) {
+                Ok(date) => Some(
+                    style(format!(
+                        "\u{2192} {}",
+                        date.format(&format!("%A {}", DateFormat::get().get_pattern()))
+                    ))
+                    .green(),
+                ),
+                Err(_) => Some(style("Invalid date".to_string()).red()),
+            },
+            FormField::Priority => match self.get_priority_error() {
+                true => Some(style(format!("Priority from 0 to {}", MAXPRIORITY)).red()),
+                false => None,
+            },
+        }
+    }
+    fn get_priority_error(&self) -> bool {
+        self.priority
+            .trim()
+            .parse::<u32>()
+            .map_or(true, |priority| priority > MAXPRIORITY)
+    }
+    fn is_valid(&self, field: FormField) -> bool {
+        match field {
+            FormField::Title => true,
+            FormField::Due => validate_due_date(&self.due).is_ok(),
+            FormField::Start => {
+                self.start.trim().is_empty() || convert_str_valid_date(&self.start).is_ok()
             }
+            FormField::Priority => !self.get_priority_error(),
         }
-        KeyEvent::ToggleRead => Ok(Some((pos_fixed, Action::ToggleRead))),
-        KeyEvent::IncreasePriority => Ok(Some((pos_fixed, Action::IncreasePriority))),
-        KeyEvent::DecreasePriority => Ok(Some((pos_fixed, Action::DecreasePriority))),
-        KeyEvent::Edit => Ok(Some((pos_fixed, Action::Edit))),
-        KeyEvent::Add => Ok(Some((pos_fixed, Action::Add))),
-        KeyEvent::NavigateDown => {
-            screen_navigate_todos(todos, add_usize_module(pos_fixed, size_todos))
+    }
+}
+
+// Inline edit form drawn in the detail pane of the full-screen mode, next to the list
+// Returns false if the form was cancelled, the values are only valid otherwise
+pub fn input_todo_form(screen: &mut Screen, heading: &str, form: &mut TodoForm) -> Result<bool> {
+    let term = Term::stdout();
+    let mut active = 0;
+    loop {
+        let mut lines = vec![style(heading).bold().to_string(), String::new()];
+        for (idx, &field) in FORM_FIELDS.iter().enumerate() {
+            let label = match field {
+                FormField::Title => "Title:   ",
+                FormField::Due => "Due:     ",
+                FormField::Start => "Start:   ",
+                FormField::Priority => "Priority:",
+            };
+            let value = form.get_value(field).clone();
+            lines.push(match idx == active {
+                true => format!(
+                    "> {} {}{}",
+                    style(label).bold(),
+                    value,
+                    style(" ").reverse()
+                ),
+                false => format!("  {} {}", label, value),
+            });
+            if let Some(preview) = form.get_preview(field) {
+                lines.push(format!("            {}", preview));
+            }
         }
-        KeyEvent::NavigateUp => {
-            screen_navigate_todos(todos, sub_usize_module(pos_fixed, size_todos))
+        lines.push(String::new());
+        lines.push(
+            style(match FORM_FIELDS[active] {
+                FormField::Title => "With optional #tags".to_string(),
+                FormField::Due => format!(
+                    "{}, tomorrow, friday, +2w, every ... [14:00, 9am]",
+                    DateFormat::get().get_hint()
+                ),
+                FormField::Start => {
+                    format!("{}, tomorrow, in 3 days...", DateFormat::get().get_hint())
+                }
+                FormField::Priority => format!("0 to {}", MAXPRIORITY),
+            })
+            .dim()
+            .to_string(),
+        );
+        draw_panes(screen, lines, FORM_MENU)?;
+        let value = form.get_value(FORM_FIELDS[active]);
+        match term.read_key().with_context(|| "Error reading key!")? {
+            Key::Escape => return Ok(false),
+            // Saving moves to the first invalid field, if any
+            Key::Enter => match FORM_FIELDS.iter().position(|&field| !form.is_valid(field)) {
+                Some(idx) => active = idx,
+                None => return Ok(true),
+            },
+            Key::Tab | Key::ArrowDown => active = add_usize_module(active, FORM_FIELDS.len()),
+            Key::BackTab | Key::ArrowUp => active = sub_usize_module(active, FORM_FIELDS.len()),
+            Key::Backspace => {
+                value.pop();
+            }
+            Key::Char(c) if !c.is_control() => value.push(c),
+            _ => continue,
         }
-        KeyEvent::IncreaseProgress => Ok(Some((pos_fixed, Action::IncreaseProgress))),
-        KeyEvent::DecreaseProgress => Ok(Some((pos_fixed, Action::DecreaseProgress))),
-        KeyEvent::Export => {
-            clear_menu()?;
-            confirm_message("Exporting...")?;
-            std::thread::sleep(std::time::Duration::from_millis(400)); // Wait for the message to be visible
-            Ok(Some((pos_fixed, Action::Export)))
+    }
+}
+
+// Live search prompt: the list is narrowed as the query is typed
+// Enter keeps the query, Escape clears it
+fn input_search(
+    todos: &[Todo],
+    header: Option<&str>,
+    search: &mut String,
+    screen: &mut Screen,
+) -> Result<()> {
+    let term = Term::stdout();
+    loop {
+        let visible = get_visible_todos(todos, search);
+        let prompt = format!(
+            "\n/{}\n{}",
+            search,
+            style("\u{21B5}: keep search   Esc: clear search").dim()
+        );
+        draw_todos_screen(
+            screen,
+            todos,
+            &visible,
+            visible.first().copied(),
+            header,
+            search,
+            &prompt,
+        )?;
+        match term.read_key().with_context(|| "Error reading key!")? {
+            Key::Enter => return Ok(()),
+            Key::Escape => {
+                search.clear();
+                return Ok(());
+            }
+            Key::Backspace => {
+                search.pop();
+            }
+            Key::Char(c) if !c.is_control() => search.push(c),
+            _ => continue,
+        }
+    }
+}
+
+// Archive browser, the archived TODOs can be searched and viewed
+// Returns the index of the TODO to restore, None goes back
+pub fn screen_archive(
+    todos: &[Todo],
+    position: usize,
+    search: &mut String,
+) -> Result<Option<usize>> {
+    let term = Term::stdout();
+    let header = format!("Archive ({} TODOs)", todos.len());
+    let mut position = position;
+    let mut screen = Screen::default();
+    loop {
+        hide_cursor()?;
+        let visible = get_visible_todos(todos, search);
+        let row = visible
+            .iter()
+            .position(|&idx| idx >= position)
+            .unwrap_or(get_pos_overflow(position, visible.len()));
+        let selected = visible.get(row).copied();
+        let page = draw_todos_screen(
+            &mut screen,
+            todos,
+            &visible,
+            selected,
+            Some(&header),
+            search,
+            ARCHIVE_MENU,
+        )?;
+        match term.read_key().with_context(|| "Error reading key!")? {
+            Key::Enter | Key::Backspace => {
+                show_cursor()?;
+                return Ok(None);
+            }
+            Key::ArrowDown => {
+                position = visible
+                    .get(add_usize_module(row, visible.len()))
+                    .copied()
+                    .unwrap_or(0)
+            }
+            Key::ArrowUp => {
+                position = visible
+                    .get(sub_usize_module(row, visible.len()))
+                    .copied()
+                    .unwrap_or(0)
+            }
+            key @ (Key::PageUp | Key::PageDown | Key::Home | Key::End) => {
+                if let Some(page_move) = get_page_move(&key) {
+                    let target = get_page_row(page_move, row, page, visible.len());
+                    position = visible.get(target).copied().unwrap_or(0);
+                }
+            }
+            Key::Char('/') => {
+                show_cursor()?;
+                input_search(todos, Some(&header), search, &mut screen)?;
+                position = 0;
+            }
+            Key::Escape => search.clear(),
+            Key::Char('v') => {
+                if let Some(idx) = selected {
+                    clear_term()?;
+                    write_todo_details(&todos[idx])?;
+                    wait_any_key()?;
+                    screen.invalidate();
+                }
+            }
+            Key::Char('r') => {
+                if selected.is_some() {
+                    show_cursor()?;
+                    return Ok(selected);
+                }
+            }
+            _ => continue,
+        }
+    }
+}
+
+// Paging keys: PageUp/PageDown move by a page, Home/End go to the first/last TODO
+fn get_page_move(key: &Key) -> Option<PageMove> {
+    match key {
+        Key::PageUp => Some(PageMove::Up),
+        Key::PageDown => Some(PageMove::Down),
+        Key::Home => Some(PageMove::First),
+        Key::End => Some(PageMove::Last),
+        _ => None,
+    }
+}
+
+// Row selected after a page move, a page being the number of TODOs shown
+fn get_page_row(page_move: PageMove, row: usize, page: usize, count: usize) -> usize {
+    let last = count.saturating_sub(1);
+    match page_move {
+        PageMove::Up => row.saturating_sub(page.max(1)),
+        PageMove::Down => (row + page.max(1)).min(last),
+        PageMove::First => 0,
+        PageMove::Last => last,
+    }
+}
+
+// Returns the action on the selected TODO, or reloads if no TODO is visible
+fn on_selected(selected: Option<usize>, action: Action) -> Option<(usize, Action)> {
+    match selected {
+        Some(pos) => Some((pos, action)),
+        None => Some((0, Action::Reload)),
+    }
+}
+
+// Main screen loop, draws TODOs and menu and handles the moves of the selection, other actions are returned to service
+// The optional header is written above the list (e.g. active filter)
+// Only the TODOs matching the search query are shown, the returned position is an index in todos
+// The screen keeps the frame between two calls, so that an action updating a TODO only redraws its row
+pub fn screen_navigate_todos(
+    todos: &[Todo],
+    position: usize,
+    header: Option<&str>,
+    search: &mut String,
+    screen: &mut Screen,
+    keymap: &Keymap,
+) -> Result<Option<(usize, Action)>> {
+    hide_cursor()?;
+    let mut position = position;
+    loop {
+        let visible = get_visible_todos(todos, search);
+        let size_visible = visible.len();
+        // Row of the selection in the visible list, the closest visible TODO if the position is hidden
+        let row = visible
+            .iter()
+            .position(|&idx| idx >= position)
+            .unwrap_or(get_pos_overflow(position, size_visible));
+        let selected = visible.get(row).copied();
+        let pos_fixed = selected.unwrap_or(0);
+        let menu = match screen.full_screen {
+            true => keymap.get_short_menu(),
+            false => keymap.get_menu(Term::stdout().size().1 as usize),
+        };
+        let page = draw_todos_screen(screen, todos, &visible, selected, header, search, &menu)?;
+        let navigation = match wait_key_event(keymap)? {
+            KeyEvent::NavigateDown | KeyEvent::NextMatch => {
+                position = visible
+                    .get(add_usize_module(row, size_visible))
+                    .copied()
+                    .unwrap_or(0);
+                continue;
+            }
+            KeyEvent::NavigateUp | KeyEvent::PreviousMatch => {
+                position = visible
+                    .get(sub_usize_module(row, size_visible))
+                    .copied()
+                    .unwrap_or(0);
+                continue;
+            }
+            KeyEvent::Page(page_move) => {
+                position = visible
+                    .get(get_page_row(page_move, row, page, size_visible))
+                    .copied()
+                    .unwrap_or(0);
+                continue;
+            }
+            KeyEvent::Search => {
+                show_cursor()?;
+                input_search(todos, header, search, screen)?;
+                hide_cursor()?;
+                position = get_visible_todos(todos, search)
+                    .first()
+                    .copied()
+                    .unwrap_or(0);
+                continue;
+            }
+            KeyEvent::ClearSearch => {
+                search.clear();
+                position = pos_fixed;
+                continue;
+            }
+            KeyEvent::Back => {
+                show_cursor()?;
+                None
+            }
+            KeyEvent::Sort => match screen.wait_sort_key(keymap)? {
+                Some(action) => Some((0, action)),
+                None => Some((pos_fixed, Action::Reload)),
+            },
+            KeyEvent::Delete => match selected {
+                None => Some((pos_fixed, Action::Reload)),
+                Some(_) => match screen.confirm("Confirm deletion?")? {
+                    true => Some((pos_fixed, Action::Delete)),
+                    false => Some((pos_fixed, Action::Reload)),
+                },
+            },
+            KeyEvent::DeleteCompleted => match screen.confirm("Archive all completed TODOs?")? {
+                true => Some((pos_fixed, Action::DeleteCompleted)),
+                false => Some((pos_fixed, Action::Reload)),
+            },
+            KeyEvent::Import => match screen.confirm("Overwrite TODOs with the markdown file?")? {
+                true => Some((pos_fixed, Action::Import)),
+                false => Some((pos_fixed, Action::Reload)),
+            },
+            KeyEvent::Export => {
+                screen.clear_menu()?;
+                flash_message("Exporting...")?;
+                Some((pos_fixed, Action::Export))
+            }
+            KeyEvent::ToggleRead => on_selected(selected, Action::ToggleRead),
+            KeyEvent::IncreasePriority => on_selected(selected, Action::IncreasePriority),
+            KeyEvent::DecreasePriority => on_selected(selected, Action::DecreasePriority),
+            KeyEvent::IncreaseProgress => on_selected(selected, Action::IncreaseProgress),
+            KeyEvent::DecreaseProgress => on_selected(selected, Action::DecreaseProgress),
+            KeyEvent::Edit => on_selected(selected, Action::Edit),
+            KeyEvent::Add => Some((pos_fixed, Action::Add)),
+            KeyEvent::FilterTag => Some((0, Action::FilterTag)),
+            KeyEvent::Filter => Some((0, Action::Filter)),
+            KeyEvent::SelectView => Some((0, Action::SelectView)),
+            KeyEvent::BrowseArchive => Some((pos_fixed, Action::BrowseArchive)),
+            KeyEvent::ToggleScheduled => Some((pos_fixed, Action::ToggleScheduled)),
+            KeyEvent::ToggleToday => Some((0, Action::ToggleToday)),
+            KeyEvent::Undo => Some((pos_fixed, Action::Undo)),
+            KeyEvent::Redo => Some((pos_fixed, Action::Redo)),
+            KeyEvent::SwitchList => Some((0, Action::SwitchList)),
+            KeyEvent::MoveToList => on_selected(selected, Action::MoveToList),
+            KeyEvent::EditChecklist => on_selected(selected, Action::EditChecklist),
+            KeyEvent::EditNotes => on_selected(selected, Action::EditNotes),
+            KeyEvent::ShowDetails => on_selected(selected, Action::ShowDetails),
+        };
+        // Only the actions updating TODOs in place, or in the edit form of the full-screen mode,
+        // leave the frame untouched
+        match &navigation {
+            Some((_, action)) if action.is_in_place() => (),
+            Some((_, Action::Add | Action::Edit)) if screen.full_screen => (),
+            _ => screen.invalidate(),
         }
+        return Ok(navigation);
     }
 }
 
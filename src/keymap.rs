@@ -0,0 +1,618 @@
+use crate::{
+    model::{KeyEvent, PageMove, SortEvent, SortKey},
+    storage::{get_config_setting, get_config_settings, lookup_setting},
+};
+use anyhow::{bail, Result};
+use console::{measure_text_width, pad_str, Alignment, Key};
+
+// Bindings are defined in the config file as KEY_<EVENT>="<keys>, <keys>", on top of the KEYMAP preset
+// Keys are separated by spaces, a word which is not a key name is a sequence of characters, e.g. "dd"
+const KEY_PREFIX: &str = "KEY_";
+
+// Events of the list screen with their name in the configuration
+const LIST_EVENTS: [(&str, KeyEvent); 36] = [
+    ("BACK", KeyEvent::Back),
+    ("SORT", KeyEvent::Sort),
+    ("NAVIGATE_UP", KeyEvent::NavigateUp),
+    ("NAVIGATE_DOWN", KeyEvent::NavigateDown),
+    ("PAGE_UP", KeyEvent::Page(PageMove::Up)),
+    ("PAGE_DOWN", KeyEvent::Page(PageMove::Down)),
+    ("FIRST", KeyEvent::Page(PageMove::First)),
+    ("LAST", KeyEvent::Page(PageMove::Last)),
+    ("TOGGLE_READ", KeyEvent::ToggleRead),
+    ("DELETE", KeyEvent::Delete),
+    ("INCREASE_PRIORITY", KeyEvent::IncreasePriority),
+    ("DECREASE_PRIORITY", KeyEvent::DecreasePriority),
+    ("INCREASE_PROGRESS", KeyEvent::IncreaseProgress),
+    ("DECREASE_PROGRESS", KeyEvent::DecreaseProgress),
+    ("EDIT", KeyEvent::Edit),
+    ("ADD", KeyEvent::Add),
+    ("EXPORT", KeyEvent::Export),
+    ("IMPORT", KeyEvent::Import),
+    ("ARCHIVE_COMPLETED", KeyEvent::DeleteCompleted),
+    ("FILTER_TAG", KeyEvent::FilterTag),
+    ("SWITCH_LIST", KeyEvent::SwitchList),
+    ("MOVE_TO_LIST", KeyEvent::MoveToList),
+    ("EDIT_CHECKLIST", KeyEvent::EditChecklist),
+    ("EDIT_NOTES", KeyEvent::EditNotes),
+    ("SHOW_DETAILS", KeyEvent::ShowDetails),
+    ("SEARCH", KeyEvent::Search),
+    ("NEXT_MATCH", KeyEvent::NextMatch),
+    ("PREVIOUS_MATCH", KeyEvent::PreviousMatch),
+    ("CLEAR_SEARCH", KeyEvent::ClearSearch),
+    ("FILTER", KeyEvent::Filter),
+    ("SELECT_VIEW", KeyEvent::SelectView),
+    ("ARCHIVE", KeyEvent::BrowseArchive),
+    ("UNDO", KeyEvent::Undo),
+    ("REDO", KeyEvent::Redo),
+    ("SHOW_SCHEDULED", KeyEvent::ToggleScheduled),
+    ("TODAY", KeyEvent::ToggleToday),
+];
+
+// Events of the sort menu with their name in the configuration
+const SORT_EVENTS: [(&str, SortEvent); 13] = [
+    ("SORT_BY_PRIORITY", SortEvent::By(SortKey::Priority)),
+    ("SORT_BY_DUE", SortEvent::By(SortKey::Due)),
+    ("SORT_BY_CREATED", SortEvent::By(SortKey::Created)),
+    ("SORT_BY_TITLE", SortEvent::By(SortKey::Title)),
+    ("SORT_BY_PROGRESS", SortEvent::By(SortKey::Progress)),
+    ("THEN_BY_PRIORITY", SortEvent::ThenBy(SortKey::Priority)),
+    ("THEN_BY_DUE", SortEvent::ThenBy(SortKey::Due)),
+    ("THEN_BY_CREATED", SortEvent::ThenBy(SortKey::Created)),
+    ("THEN_BY_TITLE", SortEvent::ThenBy(SortKey::Title)),
+    ("THEN_BY_PROGRESS", SortEvent::ThenBy(SortKey::Progress)),
+    ("REVERSE_SORT", SortEvent::Reverse),
+    ("COMPLETED_LAST", SortEvent::ToggleCompletedLast),
+    ("SORT_BACK", SortEvent::Back),
+];
+
+// Default bindings, in the same format as the configuration
+const DEFAULT_PRESET: [(&str, &str); 49] = [
+    ("BACK", "enter"),
+    ("SORT", "s"),
+    ("NAVIGATE_UP", "up"),
+    ("NAVIGATE_DOWN", "down"),
+    ("PAGE_UP", "pageup"),
+    ("PAGE_DOWN", "pagedown"),
+    ("FIRST", "home"),
+    ("LAST", "end"),
+    ("TOGGLE_READ", "x"),
+    ("DELETE", "z"),
+    ("INCREASE_PRIORITY", "+"),
+    ("DECREASE_PRIORITY", "-"),
+    ("INCREASE_PROGRESS", "right"),
+    ("DECREASE_PROGRESS", "left"),
+    ("EDIT", "e"),
+    ("ADD", "a"),
+    ("EXPORT", "m"),
+    ("IMPORT", "M"),
+    ("ARCHIVE_COMPLETED", "Z"),
+    ("FILTER_TAG", "t"),
+    ("SWITCH_LIST", "l"),
+    ("MOVE_TO_LIST", "L"),
+    ("EDIT_CHECKLIST", "c"),
+    ("EDIT_NOTES", "E"),
+    ("SHOW_DETAILS", "v"),
+    ("SEARCH", "/"),
+    ("NEXT_MATCH", "n"),
+    ("PREVIOUS_MATCH", "N"),
+    ("CLEAR_SEARCH", "esc"),
+    ("FILTER", "f"),
+    ("SELECT_VIEW", "V"),
+    ("ARCHIVE", "A"),
+    ("UNDO", "u"),
+    ("REDO", "ctrl-r"),
+    ("SHOW_SCHEDULED", "h"),
+    ("TODAY", "T"),
+    ("SORT_BY_PRIORITY", "p"),
+    ("SORT_BY_DUE", "d"),
+    ("SORT_BY_CREATED", "c"),
+    ("SORT_BY_TITLE", "t"),
+    ("SORT_BY_PROGRESS", "g"),
+    ("THEN_BY_PRIORITY", "P"),
+    ("THEN_BY_DUE", "D"),
+    ("THEN_BY_CREATED", "C"),
+    ("THEN_BY_TITLE", "T"),
+    ("THEN_BY_PROGRESS", "G"),
+    ("REVERSE_SORT", "r"),
+    ("COMPLETED_LAST", "m"),
+    ("SORT_BACK", "backspace"),
+];
+
+// Vim-style bindings replacing the default ones, h/l change the progress like the arrows
+const VIM_PRESET: [(&str, &str); 15] = [
+    ("BACK", "q, enter"),
+    ("NAVIGATE_UP", "k, up"),
+    ("NAVIGATE_DOWN", "j, down"),
+    ("PAGE_UP", "ctrl-u, pageup"),
+    ("PAGE_DOWN", "ctrl-d, pagedown"),
+    ("FIRST", "gg, home"),
+    ("LAST", "G, end"),
+    ("DELETE", "dd"),
+    ("ADD", "o"),
+    ("EDIT", "i, e"),
+    ("INCREASE_PROGRESS", "l, right"),
+    ("DECREASE_PROGRESS", "h, left"),
+    ("SWITCH_LIST", "gt"),
+    ("SHOW_SCHEDULED", "H"),
+    ("SORT_BACK", "esc, backspace"),
+];
+
+// Entries of the menu of the list screen, the keys of all the events of an entry are shown
+const MENU_ENTRIES: [(&[KeyEvent], &str); 27] = [
+    (&[KeyEvent::Add], "add"),
+    (
+        &[KeyEvent::Export, KeyEvent::Import],
+        "export/import markdown",
+    ),
+    (&[KeyEvent::FilterTag], "filter by tag"),
+    (&[KeyEvent::Edit], "edit"),
+    (&[KeyEvent::ToggleRead], "toggle read/unread"),
+    (&[KeyEvent::SwitchList], "switch list"),
+    (&[KeyEvent::Sort], "sort"),
+    (
+        &[KeyEvent::IncreasePriority, KeyEvent::DecreasePriority],
+        "change priority",
+    ),
+    (&[KeyEvent::MoveToList], "move to list"),
+    (&[KeyEvent::Delete], "delete"),
+    (&[KeyEvent::DeleteCompleted], "archive all completed"),
+    (&[KeyEvent::EditChecklist], "edit checklist"),
+    (&[KeyEvent::Back], "exit"),
+    (
+        &[KeyEvent::DecreaseProgress, KeyEvent::IncreaseProgress],
+        "change progress",
+    ),
+    (&[KeyEvent::EditNotes], "edit notes"),
+    (&[KeyEvent::Search], "search"),
+    (
+        &[KeyEvent::NextMatch, KeyEvent::PreviousMatch],
+        "next/previous match",
+    ),
+    (&[KeyEvent::ClearSearch], "clear search"),
+    (&[KeyEvent::NavigateUp, KeyEvent::NavigateDown], "move"),
+    (
+        &[
+            KeyEvent::Page(PageMove::Up),
+            KeyEvent::Page(PageMove::Down),
+            KeyEvent::Page(PageMove::First),
+            KeyEvent::Page(PageMove::Last),
+        ],
+        "scroll",
+    ),
+    (&[KeyEvent::ShowDetails], "view details"),
+    (&[KeyEvent::Undo, KeyEvent::Redo], "undo/redo"),
+    (&[KeyEvent::BrowseArchive], "archive"),
+    (&[KeyEvent::Filter], "filter"),
+    (&[KeyEvent::SelectView], "views"),
+    (&[KeyEvent::ToggleScheduled], "show scheduled"),
+    (&[KeyEvent::ToggleToday], "today"),
+];
+
+// Entries of the one line menu of the full-screen mode, the other keys work as well
+const SHORT_MENU_ENTRIES: [(&[KeyEvent], &str); 10] = [
+    (&[KeyEvent::Add], "add"),
+    (&[KeyEvent::Edit], "edit"),
+    (&[KeyEvent::ToggleRead], "toggle"),
+    (
+        &[KeyEvent::IncreasePriority, KeyEvent::DecreasePriority],
+        "priority",
+    ),
+    (&[KeyEvent::Delete], "delete"),
+    (&[KeyEvent::Search], "search"),
+    (&[KeyEvent::Filter], "filter"),
+    (&[KeyEvent::Sort], "sort"),
+    (&[KeyEvent::Undo], "undo"),
+    (&[KeyEvent::Back], "exit"),
+];
+
+// Entries of the sort menu
+const SORT_MENU_ENTRIES: [(&[SortEvent], &str); 9] = [
+    (&[SortEvent::By(SortKey::Priority)], "sort by priority"),
+    (
+        &[SortEvent::By(SortKey::Created)],
+        "sort by date of creation",
+    ),
+    (&[SortEvent::By(SortKey::Title)], "sort by title"),
+    (&[SortEvent::By(SortKey::Due)], "sort by due date"),
+    (&[SortEvent::By(SortKey::Progress)], "sort by progress"),
+    (
+        &[
+            SortEvent::ThenBy(SortKey::Priority),
+            SortEvent::ThenBy(SortKey::Created),
+            SortEvent::ThenBy(SortKey::Title),
+            SortEvent::ThenBy(SortKey::Due),
+            SortEvent::ThenBy(SortKey::Progress),
+        ],
+        "then by...",
+    ),
+    (&[SortEvent::Reverse], "reverse order"),
+    (&[SortEvent::ToggleCompletedLast], "completed last on/off"),
+    (&[SortEvent::Back], "go back"),
+];
+
+// Result of looking up the keys typed so far
+pub enum Lookup<E> {
+    Event(E),
+    Prefix,
+    Unbound,
+}
+
+// Key sequences bound to the events of a screen
+pub struct Bindings<E> {
+    bindings: Vec<(Vec<Key>, E)>,
+}
+impl<E: Copy + PartialEq> Bindings<E> {
+    // Reads the bindings of the events from the preset, the configuration overrides all the keys of an event
+    fn load(
+        events: &[(&str, E)],
+        preset: &[(&str, &str)],
+        config: &[(String, String)],
+    ) -> Result<Self> {
+        let mut bindings = Vec::new();
+        for (name, event) in events {
+            for keys in parse_bindings(lookup_setting(name, config, preset))? {
+                bindings.push((keys, *event));
+            }
+        }
+        Ok(Bindings { bindings })
+    }
+    pub fn lookup(&self, keys: &[Key]) -> Lookup<E> {
+        if let Some((_, event)) = self.bindings.iter().find(|(bound, _)| bound == keys) {
+            return Lookup::Event(*event);
+        }
+        match self
+            .bindings
+            .iter()
+            .any(|(bound, _)| bound.starts_with(keys))
+        {
+            true => Lookup::Prefix,
+            false => Lookup::Unbound,
+        }
+    }
+    // First keys bound to an event, as shown in the menus
+    fn get_label(&self, event: &E) -> Option<String> {
+        self.bindings
+            .iter()
+            .find(|(_, bound)| bound == event)
+            .map(|(keys, _)| format_keys(keys))
+    }
+    // Keys shown for a menu entry, None if none of its events is bound
+    fn get_entry_label(&self, events: &[E]) -> Option<String> {
+        let mut labels: Vec<String> = events
+            .iter()
+            .filter_map(|event| self.get_label(event))
+            .collect();
+        labels.dedup();
+        match labels.is_empty() {
+            true => None,
+            false => Some(labels.join("/")),
+        }
+    }
+    // Bindings which cannot be told apart: the same keys, or keys starting another binding
+    fn get_conflicts(&self, events: &[(&str, E)]) -> Vec<String> {
+        let get_name = |event: &E| {
+            events
+                .iter()
+                .find(|(_, bound)| bound == event)
+                .map_or("", |(name, _)| *name)
+        };
+        let mut conflicts = Vec::new();
+        for (idx, (keys, event)) in self.bindings.iter().enumerate() {
+            for (other_keys, other_event) in &self.bindings[idx + 1..] {
+                if keys.starts_with(other_keys) || other_keys.starts_with(keys) {
+                    conflicts.push(format!(
+                        "'{}' ({}{}) and '{}' ({}{})",
+                        format_keys(keys),
+                        KEY_PREFIX,
+                        get_name(event),
+                        format_keys(other_keys),
+                        KEY_PREFIX,
+                        get_name(other_event)
+                    ));
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+// Active bindings of the list screen and of the sort menu
+pub struct Keymap {
+    list: Bindings<KeyEvent>,
+    sort: Bindings<SortEvent>,
+}
+impl Keymap {
+    // Reads the preset selected with KEYMAP="default"|"vim" and the KEY_<EVENT> bindings of the config file
+    // Unknown key names and conflicting bindings are errors, unknown events are only reported
+    pub fn load() -> Result<Keymap> {
+        let preset = get_preset(&get_config_setting("KEYMAP").unwrap_or_default())?;
+        let names: Vec<&str> = LIST_EVENTS
+            .iter()
+            .map(|(name, _)| *name)
+            .chain(SORT_EVENTS.iter().map(|(name, _)| *name))
+            .collect();
+        Keymap::build(&preset, &get_config_settings(KEY_PREFIX, &names))
+    }
+    // Bindings of the preset overridden by the configured ones, conflicting bindings are errors
+    fn build(preset: &[(&str, &str)], config: &[(String, String)]) -> Result<Keymap> {
+        let keymap = Keymap {
+            list: Bindings::load(&LIST_EVENTS, preset, config)?,
+            sort: Bindings::load(&SORT_EVENTS, preset, config)?,
+        };
+        let conflicts: Vec<String> = keymap
+            .list
+            .get_conflicts(&LIST_EVENTS)
+            .into_iter()
+            .chain(keymap.sort.get_conflicts(&SORT_EVENTS))
+            .collect();
+        if !conflicts.is_empty() {
+            bail!("Conflicting key bindings:\n{}", conflicts.join("\n"));
+        }
+        Ok(keymap)
+    }
+    pub fn get_list_bindings(&self) -> &Bindings<KeyEvent> {
+        &self.list
+    }
+    pub fn get_sort_bindings(&self) -> &Bindings<SortEvent> {
+        &self.sort
+    }
+    // Menu of the list screen laid out in columns fitting the width, starting with a separator line
+    pub fn get_menu(&self, width: usize) -> String {
+        let entries = get_entries(&self.list, &MENU_ENTRIES);
+        format!("\n{}", layout_menu(&entries, width, true))
+    }
+    // One line menu of the full-screen mode
+    pub fn get_short_menu(&self) -> String {
+        get_entries(&self.list, &SHORT_MENU_ENTRIES).join("   ")
+    }
+    pub fn get_sort_menu(&self, width: usize) -> String {
+        let entries = get_entries(&self.sort, &SORT_MENU_ENTRIES);
+        layout_menu(&entries, width, false)
+    }
+}
+
+// Bindings of the preset selected with KEYMAP, the vim preset replaces some of the default bindings
+fn get_preset(name: &str) -> Result<Vec<(&'static str, &'static str)>> {
+    match name.to_lowercase().as_str() {
+        "" | "default" => Ok(DEFAULT_PRESET.to_vec()),
+        "vim" => {
+            let mut preset = DEFAULT_PRESET.to_vec();
+            for (name, keys) in VIM_PRESET {
+                if let Some(binding) = preset.iter_mut().find(|(key, _)| *key == name) {
+                    binding.1 = keys;
+                }
+            }
+            Ok(preset)
+        }
+        other => bail!("Unknown keymap '{}', expected 'default' or 'vim'!", other),
+    }
+}
+
+// Menu entries written as "<keys>: <description>", entries without bound key are left out
+fn get_entries<E: Copy + PartialEq>(
+    bindings: &Bindings<E>,
+    entries: &[(&[E], &str)],
+) -> Vec<String> {
+    entries
+        .iter()
+        .filter_map(|(events, description)| {
+            bindings
+                .get_entry_label(events)
+                .map(|label| format!("{}: {}", label, description))
+        })
+        .collect()
+}
+
+// Lays out the menu entries in as many aligned columns as fit in the width
+fn layout_menu(entries: &[String], width: usize, separator: bool) -> String {
+    let column_width = entries
+        .iter()
+        .map(|entry| measure_text_width(entry) + 3)
+        .max()
+        .unwrap_or(1);
+    let columns = (width / column_width).max(1);
+    let mut lines: Vec<String> = entries
+        .chunks(columns)
+        .map(|row| {
+            row.iter()
+                .map(|entry| pad_str(entry, column_width, Alignment::Left, None))
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+    if separator {
+        let length = (column_width * columns).min(width);
+        lines.insert(0, "-".repeat(length));
+    }
+    lines.join("\n")
+}
+
+// Parses the definition of the bindings of an event, e.g. "dd, ctrl-x, del"
+fn parse_bindings(definition: &str) -> Result<Vec<Vec<Key>>> {
+    definition
+        .split(',')
+        .map(|binding| binding.split_whitespace().collect::<Vec<&str>>())
+        .filter(|words| !words.is_empty())
+        .map(|words| {
+            let mut keys = Vec::new();
+            for word in words {
+                keys.extend(parse_keys(word)?);
+            }
+            Ok(keys)
+        })
+        .collect()
+}
+
+// A key name, e.g. "enter" or "ctrl-r", or a sequence of characters
+fn parse_keys(word: &str) -> Result<Vec<Key>> {
+    let key = match word.to_lowercase().as_str() {
+        "enter" => Key::Enter,
+        "esc" | "escape" => Key::Escape,
+        "backspace" => Key::Backspace,
+        "tab" => Key::Tab,
+        "backtab" => Key::BackTab,
+        "del" | "delete" => Key::Del,
+        "up" => Key::ArrowUp,
+        "down" => Key::ArrowDown,
+        "left" => Key::ArrowLeft,
+        "right" => Key::ArrowRight,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "space" => Key::Char(' '),
+        "comma" => Key::Char(','),
+        name => match name
+            .strip_prefix("ctrl-")
+            .map(|c| c.chars().collect::<Vec<char>>())
+        {
+            Some(c) if c.len() == 1 && c[0].is_ascii_lowercase() => get_ctrl_key(c[0])?,
+            Some(_) => bail!("Unknown key '{}'!", word),
+            None => return Ok(word.chars().map(Key::Char).collect()),
+        },
+    };
+    Ok(vec![key])
+}
+
+// Key read by the terminal for ctrl-<letter>, some control characters are read as named keys
+// and ctrl-c interrupts the reading
+fn get_ctrl_key(letter: char) -> Result<Key> {
+    Ok(match letter {
+        'a' => Key::Home,
+        'e' => Key::End,
+        'h' => Key::Backspace,
+        'i' => Key::Tab,
+        'j' | 'm' => Key::Enter,
+        'c' => bail!("'ctrl-c' cannot be bound!"),
+        _ => Key::Char((letter as u8 & 0x1f) as char),
+    })
+}
+
+// Writes keys as in the menus, e.g. "dd" or "^R"
+fn format_keys(keys: &[Key]) -> String {
+    keys.iter()
+        .map(|key| match key {
+            Key::Enter => "\u{21B5}".to_string(),
+            Key::Escape => "Esc".to_string(),
+            Key::Backspace => "Backspace".to_string(),
+            Key::Tab => "Tab".to_string(),
+            Key::BackTab => "BackTab".to_string(),
+            Key::Del => "Del".to_string(),
+            Key::ArrowUp => "\u{2191}".to_string(),
+            Key::ArrowDown => "\u{2193}".to_string(),
+            Key::ArrowLeft => "\u{023f4}".to_string(),
+            Key::ArrowRight => "\u{023f5}".to_string(),
+            Key::PageUp => "PgUp".to_string(),
+            Key::PageDown => "PgDn".to_string(),
+            Key::Home => "Home".to_string(),
+            Key::End => "End".to_string(),
+            Key::Char(' ') => "Space".to_string(),
+            Key::Char(c) if c.is_control() => format!("^{}", ((*c as u8) | 0x40) as char),
+            Key::Char(c) => c.to_string(),
+            _ => "?".to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(settings: &[(&str, &str)]) -> Vec<(String, String)> {
+        settings
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_keys() {
+        assert_eq!(parse_keys("enter").unwrap(), vec![Key::Enter]);
+        assert_eq!(parse_keys("PageDown").unwrap(), vec![Key::PageDown]);
+        assert_eq!(
+            parse_keys("dd").unwrap(),
+            vec![Key::Char('d'), Key::Char('d')]
+        );
+        assert_eq!(parse_keys("space").unwrap(), vec![Key::Char(' ')]);
+        assert_eq!(parse_keys("ctrl-r").unwrap(), vec![Key::Char('\x12')]);
+        assert_eq!(parse_keys("CTRL-U").unwrap(), vec![Key::Char('\x15')]);
+        assert!(parse_keys("ctrl-1").is_err());
+        assert!(parse_keys("ctrl-").is_err());
+    }
+
+    #[test]
+    fn parses_ctrl_keys_as_read_by_the_terminal() {
+        assert_eq!(parse_keys("ctrl-a").unwrap(), vec![Key::Home]);
+        assert_eq!(parse_keys("ctrl-e").unwrap(), vec![Key::End]);
+        assert_eq!(parse_keys("ctrl-h").unwrap(), vec![Key::Backspace]);
+        assert_eq!(parse_keys("ctrl-i").unwrap(), vec![Key::Tab]);
+        assert_eq!(parse_keys("ctrl-j").unwrap(), vec![Key::Enter]);
+        assert_eq!(parse_keys("ctrl-m").unwrap(), vec![Key::Enter]);
+        assert!(parse_keys("ctrl-c").is_err());
+    }
+
+    #[test]
+    fn parses_bindings() {
+        assert_eq!(
+            parse_bindings("dd, ctrl-x ,del").unwrap(),
+            vec![
+                vec![Key::Char('d'), Key::Char('d')],
+                vec![Key::Char('\x18')],
+                vec![Key::Del]
+            ]
+        );
+        assert_eq!(
+            parse_bindings("g g, g t").unwrap(),
+            vec![
+                vec![Key::Char('g'), Key::Char('g')],
+                vec![Key::Char('g'), Key::Char('t')]
+            ]
+        );
+        assert!(parse_bindings("").unwrap().is_empty());
+        assert!(parse_bindings("x, ctrl-c").is_err());
+    }
+
+    #[test]
+    fn reports_conflicts() {
+        let preset = get_preset("default").unwrap();
+        let bindings = Bindings::load(
+            &LIST_EVENTS,
+            &preset,
+            &config(&[("ADD", "d"), ("DELETE", "dd"), ("EDIT", "x")]),
+        )
+        .unwrap();
+        assert_eq!(
+            bindings.get_conflicts(&LIST_EVENTS),
+            vec![
+                "'x' (KEY_TOGGLE_READ) and 'x' (KEY_EDIT)".to_string(),
+                "'dd' (KEY_DELETE) and 'd' (KEY_ADD)".to_string()
+            ]
+        );
+        // ctrl-a is read as the home key
+        assert!(Keymap::build(&preset, &config(&[("UNDO", "ctrl-a")])).is_err());
+        assert!(Keymap::build(&preset, &config(&[("UNDO", "ctrl-b")])).is_ok());
+    }
+
+    #[test]
+    fn loads_presets_without_conflicts() {
+        for name in ["", "default", "vim", "VIM"] {
+            assert!(Keymap::build(&get_preset(name).unwrap(), &[]).is_ok());
+        }
+        assert!(get_preset("emacs").is_err());
+        let keymap = Keymap::build(&get_preset("vim").unwrap(), &[]).unwrap();
+        assert!(matches!(
+            keymap.list.lookup(&[Key::Char('d')]),
+            Lookup::Prefix
+        ));
+        assert!(matches!(
+            keymap.list.lookup(&[Key::Char('d'), Key::Char('d')]),
+            Lookup::Event(KeyEvent::Delete)
+        ));
+        assert!(matches!(
+            keymap.sort.lookup(&[Key::Escape]),
+            Lookup::Event(SortEvent::Back)
+        ));
+    }
+}
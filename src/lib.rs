@@ -1,12 +1,25 @@
+mod cli;
 mod date_utils;
+mod filter;
+mod history;
 mod io;
+mod keymap;
 mod md_utils;
+mod migration;
 mod model;
+mod report;
 mod service;
 mod storage;
+mod theme;
+mod view;
 
+pub use cli::*;
 pub use date_utils::*;
+pub use filter::*;
 pub use io::*;
+pub use keymap::*;
 pub use model::*;
 pub use service::*;
 pub use storage::*;
+pub use theme::*;
+pub use view::*;
@@ -1,24 +1,78 @@
+use clap::Parser;
 use rustdo::*;
-use std::env;
 
 fn main() {
+    let cli = Cli::parse();
+
     // Loading environment variables
-    load_env().unwrap_or(eprintln!("Error loading the configuration file!"));
+    load_env().unwrap_or_else(|_| eprintln!("Error loading the configuration file!"));
+
+    // Styles of the list, disabled with NO_COLOR, invalid definitions are reported before anything is shown
+    apply_no_color();
+    let theme = Theme::load().unwrap_or_else(|e| {
+        eprintln!("{:#}", e);
+        std::process::exit(1);
+    });
+    set_theme(theme);
 
     // Initiating database
-    let mut db = connect_db().unwrap();
+    let mut db = connect_db(&cli.list).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    // Named view from the configuration
+    let view = cli
+        .view
+        .as_deref()
+        .map(get_view)
+        .transpose()
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+
+    // Default sorting method, an invalid chain is reported like an invalid view
+    let default_sort = get_default_sort().unwrap_or_else(|e| {
+        eprintln!("{:#}", e);
+        std::process::exit(1);
+    });
+
+    // Non-interactive subcommands
+    if let Some(command) = cli.command {
+        run_command(&mut db, &cli.list, view.as_ref(), command).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        return;
+    }
+
+    // Key bindings, the conflicts are reported before the screen is drawn
+    let keymap = Keymap::load().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    // Archiving the TODOs completed long ago, only for the interactive screen so that
+    // the subcommands, in particular undo and redo, do not record a change of their own
+    auto_archive(&mut db, &cli.list).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        0
+    });
 
-    let default_sort = match env::var("DEFAULT_SORT")
-        .unwrap_or("due".to_string())
-        .as_str()
-    {
-        "priority" => SortingMethod::Priority,
-        "created" => SortingMethod::Created,
-        _ => SortingMethod::Due,
-    };
+    let full_screen = cli.full_screen || is_full_screen_configured();
 
     // Loading screen
     clear_term().unwrap_or_else(|e| eprintln!("{}", e));
-    navigate_todos(&mut db, 0, default_sort).unwrap_or_else(|e| eprintln!("{}", e));
+    navigate_todos(
+        &mut db,
+        0,
+        default_sort,
+        &cli.list,
+        view.as_ref(),
+        full_screen,
+        &keymap,
+    )
+    .unwrap_or_else(|e| eprintln!("{}", e));
     clear_term().unwrap_or_else(|e| eprintln!("{}", e));
 }
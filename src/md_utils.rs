@@ -1,50 +1,88 @@
 use crate::{
-    date_utils::FORMAT_DATE,
+    date_utils::{convert_str_recurrence, format_md_date, parse_md_date, FORMAT_TIME},
     io::{get_priority_symbol, get_progress_str},
-    model::{MyDate, Todo},
-    // MyDateTime, Progress,
-    // FORMAT_DATETIME,
+    model::{
+        extract_tags, format_tags, MyDate, MyDateTime, MyTime, Subtask, Todo, MAXPRIORITY,
+        PROGRESS_LEVELS,
+    },
+    storage::DEFAULT_LIST,
 };
-use anyhow::{
-    // bail,
-    Context,
-    Result,
-};
-// use chrono::{NaiveDate, NaiveDateTime};
-// use once_cell::sync::Lazy;
-// use regex::Regex;
-use std::{env, fs, io::Write};
+use anyhow::{bail, Context, Result};
+use chrono::NaiveTime;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::{env, fs, io::Write, path::Path};
+
+// Line of the markdown file which could not be parsed, with its line number
+pub type ImportError = (usize, String);
+
+// Location of the markdown file, lists other than the default one get a suffixed file
+fn get_md_filename(list: &str) -> String {
+    let md_filename = env::var("MD_FILE").unwrap_or("./todo.md".to_string());
+    if list == DEFAULT_LIST {
+        return md_filename;
+    }
+    let path = Path::new(&md_filename);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("todo");
+    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("md");
+    path.with_file_name(format!("{}_{}.{}", stem, list, extension))
+        .to_string_lossy()
+        .to_string()
+}
 
 // Converts a TODO to markdown format
 pub fn convert_todo_str(todo: &Todo) -> String {
     let completed_part = if todo.is_complete() { "[x]" } else { "[ ]" };
-    let title = todo.get_title();
+    let title = match todo.get_tags().is_empty() {
+        true => todo.get_title().to_string(),
+        false => format!("{} {}", todo.get_title(), format_tags(todo.get_tags())),
+    };
     let priority = get_priority_symbol(todo.get_priority());
-    let due = match todo.get_due_date() {
-        Some(MyDate(date)) => date.format(FORMAT_DATE).to_string(),
-        None => "never".to_string(),
+    let due = match (todo.get_due_date(), todo.get_due_time()) {
+        (Some(MyDate(date)), Some(MyTime(time))) => {
+            format!("{} {}", format_md_date(date), time.format(FORMAT_TIME))
+        }
+        (Some(MyDate(date)), None) => format_md_date(date),
+        (None, _) => "never".to_string(),
+    };
+    let due = match (todo.get_due_date(), todo.get_recurrence()) {
+        (Some(_), Some(rule)) => format!("{}, {}", due, rule),
+        _ => due,
     };
     let progress = get_progress_str(todo);
-    let created = todo
-        .get_created_date()
-        .get_0()
-        .format(FORMAT_DATE)
-        .to_string();
+    let created = format_md_date(&todo.get_created_date().get_0().date());
+
+    // Notes are written as indented body text, before the checklist
+    let notes: String = todo
+        .get_notes()
+        .lines()
+        .map(|line| format!("  {}\n", escape_note_line(line)))
+        .collect();
+    let subtasks: String = todo
+        .get_subtasks()
+        .iter()
+        .map(|subtask| {
+            let completed_part = if subtask.is_complete() { "[x]" } else { "[ ]" };
+            format!("  - {} {}\n", completed_part, subtask.get_title())
+        })
+        .collect();
 
     format!(
-        "- {} ({}) {} (due: {}) {} % {} % {}\n",
+        "- {} ({}) {} (due: {}) {} % {} % {}\n{}{}",
         completed_part,
         priority,
         title,
         due,
         progress,
         created,
-        todo.get_id()
+        todo.get_id(),
+        notes,
+        subtasks
     )
 }
 // Exports all TODOs from a vector to markdown
-pub fn export_to_md(todos: &[Todo]) -> Result<()> {
-    let md_filename = env::var("MD_FILE").unwrap_or("./todo.md".to_string());
+pub fn export_to_md(todos: &[Todo], list: &str) -> Result<()> {
+    let md_filename = get_md_filename(list);
     let mut md_file = fs::OpenOptions::new()
         .create(true)
         .write(true)
@@ -56,48 +94,295 @@ pub fn export_to_md(todos: &[Todo]) -> Result<()> {
     Ok(())
 }
 
-// To finish!!
-
-// pub fn extract_components_mdline(line_piece: &str) -> Vec<&str> {
-//     static RETASK: Lazy<Regex> = Lazy::new(|| {
-//         Regex::new(r"^-\s\[([\sx])\]\s\((!{1,3}|_)\)\s(.*)\s\(due:\s((?:\d{2}-\d{2}-\d{4})?)\)\s\[([#\s]{0,8})\]\s$").unwrap()
-//     });
-//     RETASK.find_iter(line_piece).map(|m| m.as_str()).collect()
-// }
-
-// pub fn parse_line_md(line: &str) -> Result<Todo> {
-//     let split_str = line.split('%').collect::<Vec<&str>>();
-//     if split_str.len() != 3 {
-//         bail!("Parsing error for todo line in .md!")
-//     }
-//     let id = split_str[2];
-//     let created = MyDateTime(NaiveDateTime::parse_from_str(
-//         split_str[1],
-//         FORMAT_DATETIME,
-//     )?);
-
-//     let blocks = extract_components_mdline(split_str[0]);
-//     if blocks.len() < 5 {
-//         bail!("Parsing error for todo line in .md!")
-//     }
-//     let completed = blocks[0].find('x').is_some();
-//     let priority = blocks[1].matches('!').count() as u32;
-//     let title = blocks[2];
-//     let due = NaiveDate::parse_from_str(blocks[3], FORMAT_DATE);
-//     let due_final = match due {
-//         Ok(due_obj) => Some(MyDate(due_obj)),
-//         Err(_) => None,
-//     };
-//     let count_hashtags = blocks[4].matches('#').count();
-//     let progress = match count_hashtags {
-//         0 => Progress::Zero,
-//         2 => Progress::Quarter,
-//         4 => Progress::Half,
-//         6 => Progress::ThreeQuarter,
-//         8 => Progress::Full,
-//         _ => bail!("Wrong progress marker!"),
-//     };
-//     Ok(Todo::from_scratch(
-//         id, title, priority, created, due_final, completed, progress,
-//     ))
-// }
+// Splits the first part of a markdown line into checkbox, priority, title, due date,
+// due time and recurrence rule (empty if none) and progress bar
+fn extract_components_mdline(line_piece: &str) -> Result<Vec<&str>> {
+    static RETASK: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"^-\s\[([\sx])\]\s\((!+|_)\)\s(.*)\s\(due:\s(\d{2}-\d{2}-\d{4}|\d{4}-\d{2}-\d{2}|never)(?:\s(\d{2}:\d{2}))?(?:,\s([^)]+))?\)\s\[([#\s]{8})\]$")
+            .unwrap()
+    });
+    match RETASK.captures(line_piece) {
+        Some(caps) => Ok(caps
+            .iter()
+            .skip(1)
+            .map(|m| m.map_or("", |m| m.as_str()))
+            .collect()),
+        None => bail!("Malformed TODO line!"),
+    }
+}
+
+// Parses a line written by convert_todo_str back into a TODO
+pub fn parse_line_md(line: &str) -> Result<Todo> {
+    // The title may contain '%' so we split from the right
+    let split_str = line.rsplitn(3, " % ").collect::<Vec<&str>>();
+    if split_str.len() != 3 {
+        bail!("Missing created date or id!")
+    }
+    let id = split_str[0].trim();
+    if id.is_empty() {
+        bail!("Missing id!")
+    }
+    let created = parse_md_date(split_str[1].trim()).with_context(|| "Invalid created date!")?;
+
+    let blocks = extract_components_mdline(split_str[2].trim_end())?;
+    let completed = blocks[0] == "x";
+    let priority = blocks[1].matches('!').count() as u32;
+    if priority > MAXPRIORITY {
+        bail!("Priority level above {}!", MAXPRIORITY)
+    }
+    let (title, tags) = extract_tags(blocks[2]);
+    if title.is_empty() {
+        bail!("Empty title!")
+    }
+    let due = match blocks[3] {
+        "never" => None,
+        date_str => Some(MyDate(
+            parse_md_date(date_str).with_context(|| "Invalid due date!")?,
+        )),
+    };
+    let due_time = match blocks[4] {
+        "" => None,
+        time_str => Some(MyTime(
+            NaiveTime::parse_from_str(time_str, FORMAT_TIME)
+                .with_context(|| "Invalid due time!")?,
+        )),
+    };
+    let recurrence = match blocks[5] {
+        "" => None,
+        rule => Some(convert_str_recurrence(rule).with_context(|| "Invalid recurrence!")?),
+    };
+    // Progress bars derived from a checklist may have any length, rounded down to a progress level
+    let progress = PROGRESS_LEVELS[blocks[6].matches('#').count() / 2].clone();
+    let mut todo = Todo::from_scratch(
+        id,
+        &title,
+        priority,
+        MyDateTime(created.and_hms_opt(0, 0, 0).unwrap_or_default()),
+        due,
+        completed,
+        progress,
+    );
+    todo.set_tags(tags);
+    todo.set_due_time(due_time);
+    todo.set_recurrence(recurrence);
+    Ok(todo)
+}
+
+// Parses an indented checklist item line written by convert_todo_str
+pub fn parse_subtask_line_md(line: &str) -> Result<Subtask> {
+    static RESUBTASK: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s+-\s\[([\sx])\]\s(.*)$").unwrap());
+    match RESUBTASK.captures(line) {
+        Some(caps) => {
+            let title = caps[2].trim();
+            if title.is_empty() {
+                bail!("Empty checklist item!")
+            }
+            Ok(Subtask::new(title, &caps[1] == "x"))
+        }
+        None => bail!("Malformed checklist item!"),
+    }
+}
+
+// Note lines which would be read as checklist items are escaped with a backslash, as in markdown
+fn escape_note_line(line: &str) -> String {
+    let content = line.trim_start();
+    match content.starts_with("- [") || content.starts_with('\\') {
+        true => format!("{}\\{}", &line[..line.len() - content.len()], content),
+        false => line.to_string(),
+    }
+}
+
+// Note line as written in the notes, without its indentation and escape
+fn unescape_note_line(line: &str) -> String {
+    let line = line.strip_prefix("  ").unwrap_or(line.trim());
+    let content = line.trim_start();
+    match content.strip_prefix('\\') {
+        Some(unescaped) => format!("{}{}", &line[..line.len() - content.len()], unescaped),
+        None => line.to_string(),
+    }
+}
+
+// Sets the collected note lines on the last TODO
+fn flush_notes(todos: &mut [Todo], notes: &mut Vec<String>) {
+    if let Some(todo) = todos.last_mut() {
+        if !notes.is_empty() {
+            todo.set_notes(notes.join("\n").trim_end());
+        }
+    }
+    notes.clear();
+}
+
+// Reads all TODOs from the markdown file, collecting the lines which could not be parsed
+pub fn import_from_md(list: &str) -> Result<(Vec<Todo>, Vec<ImportError>)> {
+    let md_filename = get_md_filename(list);
+    let content = fs::read_to_string(&md_filename)
+        .with_context(|| format!("Error reading markdown file {}!", md_filename))?;
+    Ok(parse_md(&content))
+}
+
+// Parses the content of a markdown file written by export_to_md
+fn parse_md(content: &str) -> (Vec<Todo>, Vec<ImportError>) {
+    let mut todos: Vec<Todo> = Vec::new();
+    let mut errors = Vec::new();
+    let mut parent_parsed = false; // Whether the last TODO line could be parsed
+    let mut notes: Vec<String> = Vec::new(); // Notes of the last TODO
+    for (idx, line) in content.lines().enumerate() {
+        // Indented lines are the notes and checklist items of the last TODO
+        if line.starts_with(char::is_whitespace) {
+            let parent = todos.last_mut().filter(|_| parent_parsed);
+            match (parent, parse_subtask_line_md(line)) {
+                (Some(todo), Ok(subtask)) => todo.push_subtask(subtask),
+                // Note lines looking like checklist items are escaped, so this one is malformed
+                (Some(_), Err(e)) if line.trim_start().starts_with("- [") => {
+                    errors.push((idx + 1, e.to_string()))
+                }
+                (Some(_), Err(_)) => notes.push(unescape_note_line(line)),
+                (None, _) if line.trim().is_empty() => (),
+                (None, _) => errors.push((idx + 1, "Indented line without TODO!".to_string())),
+            }
+            continue;
+        }
+        // Blank lines may separate paragraphs of notes
+        if line.trim().is_empty() {
+            if !notes.is_empty() {
+                notes.push(String::new());
+            }
+            continue;
+        }
+        flush_notes(&mut todos, &mut notes);
+        match parse_line_md(line) {
+            Ok(todo) => {
+                todos.push(todo);
+                parent_parsed = true;
+            }
+            Err(e) => {
+                errors.push((idx + 1, e.to_string()));
+                parent_parsed = false;
+            }
+        }
+    }
+    flush_notes(&mut todos, &mut notes);
+    (todos, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Progress, Recurrence};
+    use chrono::{NaiveDate, Weekday};
+    use humphrey_json::prelude::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    // The markdown file only keeps the creation date, without time
+    fn new_todo(id: &str, title: &str, priority: u32, due: Option<NaiveDate>) -> Todo {
+        let created = MyDateTime(date(2026, 10, 1).and_hms_opt(0, 0, 0).unwrap());
+        Todo::from_scratch(
+            id,
+            title,
+            priority,
+            created,
+            due.map(MyDate),
+            false,
+            Progress::Zero,
+        )
+    }
+
+    fn get_todos() -> Vec<Todo> {
+        let mut report = new_todo("a1", "Write the report", 3, Some(date(2026, 10, 20)));
+        report.set_tags(vec!["work".to_string(), "q4".to_string()]);
+        report.set_due_time(NaiveTime::from_hms_opt(14, 30, 0).map(MyTime));
+        report.set_recurrence(Some(Recurrence::Weekly(vec![Weekday::Mon, Weekday::Fri])));
+        report.set_notes(
+            "First line\n  indented line\n\n- [ ] not a checklist item\n\\ starts with a backslash",
+        );
+        report.push_subtask(Subtask::new("Draft", true));
+        report.push_subtask(Subtask::new("Review", false));
+        // Progress bar of the checklist, half of the items are done
+        report.set_progress(Progress::Half);
+        report.set_progress_log(Vec::new());
+
+        let mut rent = new_todo("b2", "Pay 5 % more for #42", 0, Some(date(2027, 1, 5)));
+        rent.set_recurrence(Some(Recurrence::Monthly(5)));
+
+        let mut done = Todo::from_scratch(
+            "c3",
+            "Buy milk",
+            1,
+            MyDateTime(date(2026, 9, 30).and_hms_opt(0, 0, 0).unwrap()),
+            None,
+            true,
+            Progress::Full,
+        );
+        done.set_tags(vec!["home".to_string()]);
+        done.set_notes("Oat milk");
+
+        let mut started = new_todo("d4", "Read a book", 0, None);
+        started.set_progress(Progress::ThreeQuarter);
+        started.set_progress_log(Vec::new());
+        vec![report, rent, done, started]
+    }
+
+    #[test]
+    fn imports_exported_todos() {
+        let todos = get_todos();
+        let markdown: String = todos.iter().map(convert_todo_str).collect();
+        let (imported, errors) = parse_md(&markdown);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(imported.len(), todos.len());
+        for (todo, imported) in todos.iter().zip(&imported) {
+            assert_eq!(imported.to_json(), todo.to_json(), "{}", todo.get_title());
+        }
+    }
+
+    #[test]
+    fn reports_malformed_lines() {
+        let markdown = "\
+- [ ] (!!!!!) Too urgent (due: never) [        ] % 2026-10-01 % a1
+  A note of the malformed TODO
+  - [ ] Item of the malformed TODO
+- [ ] (_) Call the bank (due: never) [        ] % 2026-10-01 % b2
+  - [ ]
+";
+        let (imported, errors) = parse_md(markdown);
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].get_id(), "b2");
+        assert!(imported[0].get_subtasks().is_empty());
+        let lines: Vec<usize> = errors.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, [1, 2, 3, 5]);
+    }
+
+    #[test]
+    fn imports_legacy_dates() {
+        let line = "- [ ] (_) Call the bank (due: 20-10-2026) [        ] % 01-10-2026 % e5";
+        let (imported, errors) = parse_md(line);
+        assert!(errors.is_empty());
+        assert_eq!(
+            imported[0].get_due_date().as_ref().map(|due| due.get_0()),
+            Some(date(2026, 10, 20))
+        );
+        assert_eq!(
+            imported[0].get_created_date().get_0().date(),
+            date(2026, 10, 1)
+        );
+    }
+
+    #[test]
+    fn escapes_note_lines() {
+        for line in [
+            "- [x] done",
+            "  - [ ] item",
+            "\\path",
+            "plain",
+            "  indented",
+        ] {
+            assert_eq!(
+                unescape_note_line(&format!("  {}", escape_note_line(line))),
+                line
+            );
+        }
+        assert_eq!(escape_note_line("- [ ] item"), "\\- [ ] item");
+    }
+}
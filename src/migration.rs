@@ -0,0 +1,322 @@
+use crate::{
+    date_utils::{FORMAT_DATE, FORMAT_DATETIME},
+    model::SCHEMA_VERSION,
+};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use humphrey_json::{error::ParseError, prelude::*, Value};
+use jasondb::Database;
+use std::path::Path;
+
+type Record = Vec<(String, Value)>;
+type Migration = fn(&mut Record) -> Result<()>;
+
+// Migrations from schema version n to n + 1, indexed by n
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [
+    migrate_v0_to_v1,
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+    migrate_v4_to_v5,
+    migrate_v5_to_v6,
+    migrate_v6_to_v7,
+    migrate_v7_to_v8,
+    migrate_v8_to_v9,
+];
+
+// Untyped DB entry, used to read and rewrite records written with an older schema
+pub struct RawRecord(pub Value);
+impl IntoJson for RawRecord {
+    fn to_json(&self) -> Value {
+        self.0.clone()
+    }
+}
+impl FromJson for RawRecord {
+    fn from_json(value: &Value) -> Result<Self, ParseError> {
+        Ok(RawRecord(value.clone()))
+    }
+}
+
+// Rewrites all the records of the database file to the current schema version
+// A backup of the file is made before any change, returns the number of migrated records
+pub fn migrate_database(filename: &Path) -> Result<usize> {
+    if !filename.exists() {
+        return Ok(0);
+    }
+    let mut db: Database<RawRecord> =
+        Database::new(filename).with_context(|| "Error opening database for migration!")?;
+    let outdated = db
+        .iter()
+        .filter_map(|x| x.ok())
+        .filter(|(_, RawRecord(value))| get_schema_version(value) < SCHEMA_VERSION)
+        .collect::<Vec<(String, RawRecord)>>();
+    if outdated.is_empty() {
+        return Ok(0);
+    }
+    let backup = filename.with_extension("json.bak");
+    std::fs::copy(filename, &backup)
+        .with_context(|| format!("Error backing up database to {}!", backup.display()))?;
+    // All the records are migrated before the first write, so that a failure leaves the file untouched
+    let mut migrated = Vec::new();
+    for (key, RawRecord(mut value)) in outdated {
+        migrate_record(&mut value).with_context(|| format!("Error migrating entry {}!", key))?;
+        migrated.push((key, value));
+    }
+    let count = migrated.len();
+    for (key, value) in migrated {
+        db.set(&key, RawRecord(value))
+            .with_context(|| "Error writing migrated entry!")?;
+    }
+    Ok(count)
+}
+
+// Reads the schema version of a record, records without version predate the versioning
+fn get_schema_version(value: &Value) -> u32 {
+    match value {
+        Value::Object(fields) => match get_field(fields, "schema_version") {
+            Some(Value::Number(n)) => *n as u32,
+            _ => 0,
+        },
+        _ => 0,
+    }
+}
+
+// Applies the successive migrations to a record until it reaches the current version
+pub fn migrate_record(value: &mut Value) -> Result<()> {
+    let mut version = get_schema_version(value);
+    let fields = match value {
+        Value::Object(fields) => fields,
+        _ => bail!("Entry is not an object!"),
+    };
+    while version < SCHEMA_VERSION {
+        MIGRATIONS[version as usize](fields)?;
+        version += 1;
+        set_field(fields, "schema_version", Value::Number(version as f64));
+    }
+    Ok(())
+}
+
+fn get_field<'a>(fields: &'a Record, name: &str) -> Option<&'a Value> {
+    fields
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value)
+}
+
+fn set_field(fields: &mut Record, name: &str, value: Value) {
+    match fields.iter_mut().find(|(key, _)| key == name) {
+        Some((_, old_value)) => *old_value = value,
+        None => fields.push((name.to_string(), value)),
+    }
+}
+
+// Version 1: created timestamps are stored in ISO 8601
+fn migrate_v0_to_v1(fields: &mut Record) -> Result<()> {
+    let created = match get_field(fields, "created") {
+        Some(Value::String(s)) => match parse_legacy_datetime(s) {
+            Some(created) => created,
+            None => bail!("Invalid creation date '{}'!", s),
+        },
+        _ => bail!("Missing creation date!"),
+    };
+    set_field(
+        fields,
+        "created",
+        Value::String(created.format(FORMAT_DATETIME).to_string()),
+    );
+    Ok(())
+}
+
+// Version 2: TODOs carry a list of tags
+fn migrate_v1_to_v2(fields: &mut Record) -> Result<()> {
+    if get_field(fields, "tags").is_none() {
+        set_field(fields, "tags", Value::Array(Vec::new()));
+    }
+    Ok(())
+}
+
+// Version 3: TODOs may carry a recurrence rule
+fn migrate_v2_to_v3(fields: &mut Record) -> Result<()> {
+    if get_field(fields, "recurrence").is_none() {
+        set_field(fields, "recurrence", Value::Null);
+    }
+    Ok(())
+}
+
+// Version 4: TODOs carry a checklist of subtasks
+fn migrate_v3_to_v4(fields: &mut Record) -> Result<()> {
+    if get_field(fields, "subtasks").is_none() {
+        set_field(fields, "subtasks", Value::Array(Vec::new()));
+    }
+    Ok(())
+}
+
+// Version 5: TODOs carry free-text notes
+fn migrate_v4_to_v5(fields: &mut Record) -> Result<()> {
+    if get_field(fields, "notes").is_none() {
+        set_field(fields, "notes", Value::String(String::new()));
+    }
+    Ok(())
+}
+
+// Version 6: completed TODOs may carry their completion date, unknown for older records
+fn migrate_v5_to_v6(fields: &mut Record) -> Result<()> {
+    if get_field(fields, "completed_at").is_none() {
+        set_field(fields, "completed_at", Value::Null);
+    }
+    Ok(())
+}
+
+// Version 7: TODOs carry the log of their progress changes
+fn migrate_v6_to_v7(fields: &mut Record) -> Result<()> {
+    if get_field(fields, "progress_log").is_none() {
+        set_field(fields, "progress_log", Value::Array(Vec::new()));
+    }
+    Ok(())
+}
+
+// Version 8: due dates may have a time of day, older records are due at the end of the day
+fn migrate_v7_to_v8(fields: &mut Record) -> Result<()> {
+    if get_field(fields, "due_time").is_none() {
+        set_field(fields, "due_time", Value::Null);
+    }
+    Ok(())
+}
+
+// Version 9: TODOs may have a start date before which they are hidden
+fn migrate_v8_to_v9(fields: &mut Record) -> Result<()> {
+    if get_field(fields, "scheduled").is_none() {
+        set_field(fields, "scheduled", Value::Null);
+    }
+    Ok(())
+}
+
+// Recovers a timestamp written with the legacy format "%d-%m-%Y %h-%m-%s" (%h is the month
+// abbreviation, %s the epoch timestamp)
+// The exact time is read from the epoch timestamp in local time, falling back to the date alone
+fn parse_legacy_datetime(s: &str) -> Option<NaiveDateTime> {
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(s, FORMAT_DATETIME) {
+        return Some(datetime);
+    }
+    let (date_part, time_part) = s.split_once(' ')?;
+    let from_epoch = time_part
+        .rsplit('-')
+        .next()
+        .and_then(|secs| secs.parse::<i64>().ok())
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .map(|datetime| datetime.with_timezone(&Local).naive_local());
+    from_epoch.or_else(|| {
+        NaiveDate::parse_from_str(date_part, FORMAT_DATE)
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Progress, Recurrence, Todo};
+
+    fn get_string<'a>(value: &'a Value, name: &str) -> Option<&'a str> {
+        match value {
+            Value::Object(fields) => match get_field(fields, name) {
+                Some(Value::String(s)) => Some(s),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn migrates_v0_records() {
+        let mut value = Value::parse(
+            r#"{"id": "a1", "title": "Write the report", "priority": 2,
+                "created": "17-10-2026 Oct-10-1792224000", "due": "20-10-2026",
+                "completed": false, "progress": "Half"}"#,
+        )
+        .unwrap();
+        migrate_record(&mut value).unwrap();
+        assert_eq!(get_schema_version(&value), SCHEMA_VERSION);
+        let todo = Todo::from_json(&value).unwrap();
+        assert_eq!(todo.get_id(), "a1");
+        assert_eq!(todo.get_title(), "Write the report");
+        assert_eq!(todo.get_priority(), 2);
+        // The epoch timestamp is read in local time, like every other creation date
+        let created = DateTime::from_timestamp(1792224000, 0)
+            .unwrap()
+            .with_timezone(&Local)
+            .naive_local();
+        assert_eq!(todo.get_created_date().get_0(), created);
+        assert_eq!(
+            todo.get_due_date().as_ref().map(|due| due.get_0()),
+            NaiveDate::from_ymd_opt(2026, 10, 20)
+        );
+        assert!(todo.get_due_time().is_none());
+        assert!(todo.get_scheduled_date().is_none());
+        assert!(!todo.is_complete());
+        assert_eq!(todo.get_progress(), &Progress::Half);
+        assert!(todo.get_tags().is_empty());
+        assert!(todo.get_recurrence().is_none());
+        assert!(todo.get_subtasks().is_empty());
+        assert_eq!(todo.get_notes(), "");
+        assert!(todo.get_completed_date().is_none());
+        assert!(todo.get_progress_log().is_empty());
+    }
+
+    #[test]
+    fn migrates_legacy_dates_without_timestamp() {
+        let mut value = Value::parse(r#"{"id": "a1", "created": "17-10-2026 Oct-10-x"}"#).unwrap();
+        migrate_record(&mut value).unwrap();
+        assert_eq!(get_string(&value, "created"), Some("2026-10-17T00:00:00"));
+    }
+
+    #[test]
+    fn rejects_invalid_creation_dates() {
+        let mut value = Value::parse(r#"{"id": "a1", "created": "yesterday"}"#).unwrap();
+        assert!(migrate_record(&mut value).is_err());
+        let mut value = Value::parse(r#"{"id": "a1"}"#).unwrap();
+        assert!(migrate_record(&mut value).is_err());
+    }
+
+    #[test]
+    fn migrates_intermediate_records() {
+        // Version 4 record: tags, recurrence and checklist but no notes yet
+        let mut value = Value::parse(
+            r#"{"id": "b2", "title": "Water the plants", "priority": 1,
+                "created": "2026-10-01T08:30:00", "due": "02-10-2026", "completed": true,
+                "progress": "Full", "tags": ["home"], "recurrence": "every 3 days",
+                "subtasks": [{"title": "Balcony", "completed": true}], "schema_version": 4}"#,
+        )
+        .unwrap();
+        migrate_record(&mut value).unwrap();
+        assert_eq!(get_schema_version(&value), SCHEMA_VERSION);
+        let todo = Todo::from_json(&value).unwrap();
+        assert_eq!(todo.get_id(), "b2");
+        assert_eq!(todo.get_title(), "Water the plants");
+        assert_eq!(todo.get_priority(), 1);
+        assert_eq!(
+            todo.get_created_date().get_0(),
+            NaiveDate::from_ymd_opt(2026, 10, 1)
+                .unwrap()
+                .and_hms_opt(8, 30, 0)
+                .unwrap()
+        );
+        assert_eq!(
+            todo.get_due_date().as_ref().map(|due| due.get_0()),
+            NaiveDate::from_ymd_opt(2026, 10, 2)
+        );
+        assert!(todo.get_due_time().is_none());
+        assert!(todo.get_scheduled_date().is_none());
+        assert!(todo.is_complete());
+        assert_eq!(todo.get_progress(), &Progress::Full);
+        assert_eq!(todo.get_tags(), ["home".to_string()]);
+        assert_eq!(todo.get_recurrence(), &Some(Recurrence::EveryNDays(3)));
+        assert_eq!(todo.get_subtasks().len(), 1);
+        assert_eq!(todo.get_subtasks()[0].get_title(), "Balcony");
+        assert!(todo.get_subtasks()[0].is_complete());
+        assert_eq!(todo.get_notes(), "");
+        // The completion date of older records is unknown
+        assert!(todo.get_completed_date().is_none());
+        assert!(todo.get_progress_log().is_empty());
+    }
+}
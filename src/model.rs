@@ -1,7 +1,10 @@
 use crate::date_utils::*;
+use anyhow::{bail, Result};
 use chrono::prelude::*;
-use chrono::NaiveDate;
+use chrono::{NaiveDate, Weekday};
 use humphrey_json::{error::ParseError, prelude::*, Value};
+use once_cell::sync::Lazy;
+use regex::Regex;
 use uuid::Uuid;
 
 pub type TodoCollection = Vec<Todo>;
@@ -9,9 +12,11 @@ pub type TodoCollectionRef = [Todo];
 
 // Maximum priority level (inclusive)
 pub const MAXPRIORITY: u32 = 3;
+// Version of the stored TODO records, bumped with each migration in migration.rs
+pub const SCHEMA_VERSION: u32 = 9;
 
 // The progress enum is used to track the progession of a given TODO
-#[derive(FromJson, IntoJson, PartialEq, Debug)]
+#[derive(FromJson, IntoJson, PartialEq, Debug, Clone)]
 pub enum Progress {
     Zero = 0,
     Quarter = 25,
@@ -19,6 +24,14 @@ pub enum Progress {
     ThreeQuarter = 75,
     Full = 100,
 }
+// All progress levels, in increasing order
+pub const PROGRESS_LEVELS: [Progress; 5] = [
+    Progress::Zero,
+    Progress::Quarter,
+    Progress::Half,
+    Progress::ThreeQuarter,
+    Progress::Full,
+];
 // Methods to simply edit the progress status of the enum
 impl Progress {
     pub fn up(&self) -> Self {
@@ -44,6 +57,7 @@ impl Progress {
 }
 
 // Some wrappers for chrono's NaiveDate, NaiveDateTime. Required to implement easy JSON serialization
+#[derive(Clone)]
 pub struct MyDateTime(pub NaiveDateTime);
 impl MyDateTime {
     pub fn get_0(&self) -> NaiveDateTime {
@@ -77,6 +91,7 @@ impl std::fmt::Debug for MyDateTime {
     }
 }
 
+#[derive(Clone)]
 pub struct MyDate(pub NaiveDate);
 impl MyDate {
     pub fn get_0(&self) -> NaiveDate {
@@ -108,6 +123,119 @@ impl std::fmt::Debug for MyDate {
     }
 }
 
+#[derive(Clone)]
+pub struct MyTime(pub NaiveTime);
+impl MyTime {
+    pub fn get_0(&self) -> NaiveTime {
+        self.0
+    }
+}
+impl IntoJson for MyTime {
+    fn to_json(&self) -> Value {
+        Value::String(self.0.format(FORMAT_TIME).to_string())
+    }
+}
+impl FromJson for MyTime {
+    fn from_json(value: &Value) -> Result<Self, ParseError> {
+        match value {
+            Value::String(s) => match NaiveTime::parse_from_str(s, FORMAT_TIME) {
+                Ok(time) => Ok(MyTime(time)),
+                Err(_) => Err(ParseError::TypeError),
+            },
+            _ => Err(ParseError::TypeError),
+        }
+    }
+}
+impl std::fmt::Debug for MyTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MyTime").field("Time", &self.0).finish()
+    }
+}
+
+// State of a due date compared to the current time, used for its color
+#[derive(PartialEq, Debug)]
+pub enum DueStatus {
+    Upcoming,
+    Soon,
+    Overdue,
+}
+
+// Recurrence rule of a TODO, the next occurrence is created when the TODO is completed
+#[derive(PartialEq, Debug, Clone)]
+pub enum Recurrence {
+    Daily,
+    Weekly(Vec<Weekday>),
+    EveryNDays(u32),
+    Monthly(u32),
+}
+// The rule is written in the same syntax as the one typed in the due date prompt
+impl std::fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "every day"),
+            Recurrence::Weekly(days) => {
+                let days_str = days
+                    .iter()
+                    .map(|day| ALLOWEDWEEKDAY[day.num_days_from_monday() as usize])
+                    .collect::<Vec<&str>>()
+                    .join(",");
+                write!(f, "every {}", days_str)
+            }
+            Recurrence::EveryNDays(n) => write!(f, "every {} days", n),
+            Recurrence::Monthly(day) => write!(f, "every month on {}", day),
+        }
+    }
+}
+impl IntoJson for Recurrence {
+    fn to_json(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+impl FromJson for Recurrence {
+    fn from_json(value: &Value) -> Result<Self, ParseError> {
+        match value {
+            Value::String(s) => convert_str_recurrence(s).map_err(|_| ParseError::TypeError),
+            _ => Err(ParseError::TypeError),
+        }
+    }
+}
+
+// Checklist item nested under a TODO
+#[derive(Debug, FromJson, IntoJson, Clone)]
+pub struct Subtask {
+    title: String,
+    completed: bool,
+}
+impl Subtask {
+    pub fn new(title: &str, completed: bool) -> Subtask {
+        Subtask {
+            title: title.to_string(),
+            completed,
+        }
+    }
+    pub fn get_title(&self) -> &str {
+        &self.title
+    }
+    pub fn is_complete(&self) -> bool {
+        self.completed
+    }
+}
+
+// Progress level reached by a TODO at a given time
+#[derive(Debug, FromJson, IntoJson, Clone)]
+pub struct ProgressChange {
+    date: MyDateTime,
+    progress: Progress,
+}
+impl ProgressChange {
+    pub fn get_date(&self) -> &MyDateTime {
+        &self.date
+    }
+    pub fn get_progress(&self) -> &Progress {
+        &self.progress
+    }
+}
+
 // Main object: TODO
 #[derive(Debug, FromJson, IntoJson)]
 pub struct Todo {
@@ -116,8 +244,17 @@ pub struct Todo {
     priority: u32,
     created: MyDateTime,
     due: Option<MyDate>,
+    due_time: Option<MyTime>,
+    scheduled: Option<MyDate>,
     completed: bool,
     progress: Progress,
+    tags: Vec<String>,
+    recurrence: Option<Recurrence>,
+    subtasks: Vec<Subtask>,
+    notes: String,
+    completed_at: Option<MyDateTime>,
+    progress_log: Vec<ProgressChange>,
+    schema_version: u32,
 }
 
 impl Todo {
@@ -128,20 +265,18 @@ impl Todo {
             title: title.to_owned(),
             priority,
             created: MyDateTime(Local::now().naive_local()),
-            due: {
-                match due_date_opt {
-                    None => None,
-                    Some(due_date) => {
-                        let convert_date = convert_str_valid_date(due_date);
-                        match convert_date {
-                            Ok(date) => Some(MyDate(date)),
-                            Err(_) => None,
-                        }
-                    }
-                }
-            },
+            due: parse_due_date(due_date_opt),
+            due_time: parse_due_time(due_date_opt),
+            scheduled: None,
             completed: false,
             progress: Progress::Zero,
+            tags: Vec::new(),
+            recurrence: parse_recurrence(due_date_opt),
+            subtasks: Vec::new(),
+            notes: String::new(),
+            completed_at: None,
+            progress_log: Vec::new(),
+            schema_version: SCHEMA_VERSION,
         }
     }
     pub fn from_scratch(
@@ -159,8 +294,17 @@ impl Todo {
             created,
             priority,
             due,
+            due_time: None,
+            scheduled: None,
             completed,
             progress,
+            tags: Vec::new(),
+            recurrence: None,
+            subtasks: Vec::new(),
+            notes: String::new(),
+            completed_at: None,
+            progress_log: Vec::new(),
+            schema_version: SCHEMA_VERSION,
         }
     }
     // methods to access/set private properties
@@ -176,6 +320,30 @@ impl Todo {
     pub fn get_due_date(&self) -> &Option<MyDate> {
         &self.due
     }
+    pub fn get_due_time(&self) -> &Option<MyTime> {
+        &self.due_time
+    }
+    pub fn get_scheduled_date(&self) -> &Option<MyDate> {
+        &self.scheduled
+    }
+    // Incomplete TODOs which cannot be started before a future date
+    pub fn is_scheduled_later(&self) -> bool {
+        let today = Local::now().date_naive();
+        !self.completed
+            && self
+                .scheduled
+                .as_ref()
+                .is_some_and(|MyDate(date)| *date > today)
+    }
+    // Due date and time, TODOs without due time are due at the end of the day
+    pub fn get_due_datetime(&self) -> Option<NaiveDateTime> {
+        let MyDate(date) = self.due.as_ref()?;
+        let time = match &self.due_time {
+            Some(MyTime(time)) => *time,
+            None => NaiveTime::from_hms_opt(23, 59, 59)?,
+        };
+        Some(date.and_time(time))
+    }
     pub fn get_created_date(&self) -> &MyDateTime {
         &self.created
     }
@@ -185,14 +353,160 @@ impl Todo {
     pub fn get_progress(&self) -> &Progress {
         &self.progress
     }
+    pub fn get_tags(&self) -> &[String] {
+        &self.tags
+    }
+    pub fn get_recurrence(&self) -> &Option<Recurrence> {
+        &self.recurrence
+    }
+    pub fn get_notes(&self) -> &str {
+        &self.notes
+    }
+    pub fn get_completed_date(&self) -> &Option<MyDateTime> {
+        &self.completed_at
+    }
+    pub fn get_progress_log(&self) -> &[ProgressChange] {
+        &self.progress_log
+    }
+    pub fn get_subtasks(&self) -> &[Subtask] {
+        &self.subtasks
+    }
+    // Fraction of completed checklist items, None without checklist
+    pub fn get_subtasks_ratio(&self) -> Option<f64> {
+        if self.subtasks.is_empty() {
+            return None;
+        }
+        let done = self.subtasks.iter().filter(|sub| sub.completed).count();
+        Some(done as f64 / self.subtasks.len() as f64)
+    }
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+    // Case-insensitive search in the title, tags, notes and checklist items
+    pub fn matches_text(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.title.to_lowercase().contains(&query)
+            || self.tags.iter().any(|tag| tag.contains(&query))
+            || self.notes.to_lowercase().contains(&query)
+            || self
+                .subtasks
+                .iter()
+                .any(|subtask| subtask.title.to_lowercase().contains(&query))
+    }
     pub fn set_id(&mut self, id: &str) {
         self.id = id.to_string()
     }
     pub fn set_creation_date(&mut self, date: MyDateTime) {
         self.created = date
     }
+    pub fn set_title(&mut self, title: &str) {
+        self.title = title.to_string()
+    }
+    pub fn set_due_date(&mut self, due_date_opt: Option<&str>) {
+        self.due = parse_due_date(due_date_opt);
+        self.due_time = parse_due_time(due_date_opt);
+        self.recurrence = parse_recurrence(due_date_opt)
+    }
+    // Due date entered when editing the TODO, prewritten with the recurrence rule of a recurring TODO
+    // An unchanged rule keeps the current due date instead of moving it to the first occurrence from today
+    pub fn edit_due_date(&mut self, due_date_opt: Option<&str>) {
+        match (&self.due, &self.recurrence) {
+            (Some(_), Some(rule)) if parse_recurrence(due_date_opt).as_ref() == Some(rule) => {
+                self.due_time = parse_due_time(due_date_opt)
+            }
+            _ => self.set_due_date(due_date_opt),
+        }
+    }
+    pub fn set_due_time(&mut self, due_time: Option<MyTime>) {
+        self.due_time = due_time
+    }
+    pub fn set_scheduled_date(&mut self, scheduled: Option<MyDate>) {
+        self.scheduled = scheduled
+    }
+    pub fn set_recurrence(&mut self, recurrence: Option<Recurrence>) {
+        self.recurrence = recurrence
+    }
+    pub fn set_notes(&mut self, notes: &str) {
+        self.notes = notes.to_string()
+    }
+    pub fn add_subtask(&mut self, title: &str) {
+        self.subtasks.push(Subtask::new(title, false))
+    }
+    pub fn push_subtask(&mut self, subtask: Subtask) {
+        self.subtasks.push(subtask)
+    }
+    pub fn remove_subtask(&mut self, idx: usize) {
+        if idx < self.subtasks.len() {
+            self.subtasks.remove(idx);
+        }
+    }
+    pub fn toggle_subtask(&mut self, idx: usize) {
+        if let Some(subtask) = self.subtasks.get_mut(idx) {
+            subtask.completed = !subtask.completed
+        }
+    }
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags = tags
+    }
+    pub fn set_priority(&mut self, priority: u32) {
+        self.priority = std::cmp::min(priority, MAXPRIORITY)
+    }
+    // Each change of progress is logged with its date
+    pub fn set_progress(&mut self, progress: Progress) {
+        if progress != self.progress {
+            self.progress = progress;
+            self.log_progress();
+        }
+    }
+    pub fn set_progress_log(&mut self, progress_log: Vec<ProgressChange>) {
+        self.progress_log = progress_log
+    }
+    pub fn log_progress(&mut self) {
+        self.progress_log.push(ProgressChange {
+            date: MyDateTime(Local::now().naive_local()),
+            progress: self.progress.clone(),
+        })
+    }
+    // The completion date is set when the TODO is completed and cleared when it is reopened
+    pub fn set_completed(&mut self, completed: bool) {
+        if completed != self.completed {
+            self.completed_at = match completed {
+                true => Some(MyDateTime(Local::now().naive_local())),
+                false => None,
+            };
+        }
+        self.completed = completed
+    }
+    pub fn set_completed_date(&mut self, date: Option<MyDateTime>) {
+        self.completed_at = date
+    }
     pub fn toggle_read(&mut self) {
-        self.completed = !self.completed
+        self.set_completed(!self.completed)
+    }
+    // Creates the next occurrence of a recurring TODO, due after the current due date
+    pub fn get_next_occurrence(&self) -> Option<Todo> {
+        let recurrence = self.recurrence.as_ref()?;
+        let after = match &self.due {
+            Some(MyDate(due)) => *due,
+            None => Local::now().date_naive(),
+        };
+        let mut next = Todo::new(&self.title, self.priority, None);
+        let next_due = get_next_occurrence(recurrence, after);
+        next.due = Some(MyDate(next_due));
+        next.due_time = self.due_time.clone();
+        // The start date keeps the same distance to the due date
+        next.scheduled = self
+            .scheduled
+            .as_ref()
+            .map(|MyDate(scheduled)| MyDate(*scheduled + (next_due - after)));
+        next.tags = self.tags.clone();
+        next.recurrence = Some(recurrence.clone());
+        next.subtasks = self
+            .subtasks
+            .iter()
+            .map(|sub| Subtask::new(&sub.title, false))
+            .collect();
+        Some(next)
     }
     pub fn increase_priority(&mut self) {
         self.priority = std::cmp::min(self.priority + 1, MAXPRIORITY)
@@ -201,19 +515,223 @@ impl Todo {
         self.priority = std::cmp::max(self.priority as i32 - 1, 0) as u32
     }
     pub fn increase_progress(&mut self) {
-        self.progress = self.progress.up()
+        self.set_progress(self.progress.up())
     }
     pub fn decrease_progress(&mut self) {
-        self.progress = self.progress.down()
+        self.set_progress(self.progress.down())
     }
 }
-// Useful enums to keep track of actions/results/events of our functions
-pub enum SortingMethod {
+// Converts an optional due date string to a date, invalid dates are dropped
+fn parse_due_date(due_date_opt: Option<&str>) -> Option<MyDate> {
+    match due_date_opt {
+        None => None,
+        Some(due_date) => {
+            let convert_date = convert_str_due(due_date);
+            match convert_date {
+                Ok((date, _)) => Some(MyDate(date)),
+                Err(_) => None,
+            }
+        }
+    }
+}
+
+// Converts an optional due date string to its time of day, if any
+fn parse_due_time(due_date_opt: Option<&str>) -> Option<MyTime> {
+    due_date_opt
+        .and_then(|due_date| convert_str_due(due_date).ok())
+        .and_then(|(_, time)| time.map(MyTime))
+}
+
+// Splits a title into the text and its inline #tags (lowercased, without duplicates)
+// A tag starts with a letter, so that "#42" stays in the title, and the spacing of the title is kept
+pub fn extract_tags(title: &str) -> (String, Vec<String>) {
+    static REWORD: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\s*)(\S+)").unwrap());
+    let mut text = String::new();
+    let mut tags: Vec<String> = Vec::new();
+    for caps in REWORD.captures_iter(title) {
+        let tag = caps[2].strip_prefix('#').filter(|t| {
+            t.starts_with(char::is_alphabetic)
+                && t.chars()
+                    .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        });
+        // A tag is removed with the whitespace before it
+        match tag {
+            Some(tag) => {
+                let tag = tag.to_lowercase();
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            None => text.push_str(&caps[0]),
+        }
+    }
+    (text.trim().to_string(), tags)
+}
+
+// Formats tags as inline #tags
+pub fn format_tags(tags: &[String]) -> String {
+    tags.iter()
+        .map(|tag| format!("#{}", tag))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Converts an optional due date string to a recurrence rule, if it is one
+fn parse_recurrence(due_date_opt: Option<&str>) -> Option<Recurrence> {
+    let (rule, _) = split_due_time(due_date_opt?).ok()?;
+    convert_str_recurrence(&rule).ok()
+}
+
+// Field of a TODO used to sort the list
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SortKey {
     Priority,
     Due,
     Created,
+    Title,
+    Progress,
+}
+impl SortKey {
+    // Direction used when none is given: most urgent or most recent first
+    pub fn default_direction(&self) -> SortDirection {
+        match self {
+            SortKey::Due | SortKey::Title => SortDirection::Asc,
+            SortKey::Priority | SortKey::Created | SortKey::Progress => SortDirection::Desc,
+        }
+    }
+}
+impl std::fmt::Display for SortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            SortKey::Priority => "priority",
+            SortKey::Due => "due",
+            SortKey::Created => "created",
+            SortKey::Title => "title",
+            SortKey::Progress => "progress",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+impl SortDirection {
+    pub fn reverse(&self) -> SortDirection {
+        match self {
+            SortDirection::Asc => SortDirection::Desc,
+            SortDirection::Desc => SortDirection::Asc,
+        }
+    }
+}
+
+// Chain of sort keys, each key breaks the ties of the previous ones
+// Completed TODOs are grouped after the incomplete ones unless completed_last is false
+#[derive(Clone, Debug)]
+pub struct SortingMethod {
+    keys: Vec<(SortKey, SortDirection)>,
+    completed_last: bool,
+}
+impl SortingMethod {
+    // Sorting on a single key in its default direction
+    pub fn by_key(key: SortKey) -> SortingMethod {
+        SortingMethod {
+            keys: vec![(key, key.default_direction())],
+            completed_last: true,
+        }
+    }
+    // Reads a sorting method as written in the configuration, e.g. "priority desc, due asc, title"
+    // The keyword "mixed" keeps completed TODOs among the incomplete ones
+    pub fn parse(s: &str) -> Result<SortingMethod> {
+        let mut keys = Vec::new();
+        let mut completed_last = true;
+        for item in s.to_lowercase().split(',') {
+            let words: Vec<&str> = item.split_whitespace().collect();
+            let (key, direction) = match words.as_slice() {
+                ["mixed"] => {
+                    completed_last = false;
+                    continue;
+                }
+                [key] => (parse_sort_key(key)?, None),
+                [key, direction] => (parse_sort_key(key)?, Some(parse_sort_direction(direction)?)),
+                _ => bail!("Invalid sort key '{}'!", item.trim()),
+            };
+            keys.push((key, direction.unwrap_or(key.default_direction())));
+        }
+        if keys.is_empty() {
+            bail!("No sort key!");
+        }
+        Ok(SortingMethod {
+            keys,
+            completed_last,
+        })
+    }
+    pub fn get_keys(&self) -> &[(SortKey, SortDirection)] {
+        &self.keys
+    }
+    pub fn is_completed_last(&self) -> bool {
+        self.completed_last
+    }
+    // Same chain with every direction reversed
+    pub fn reversed(&self) -> SortingMethod {
+        SortingMethod {
+            keys: self
+                .keys
+                .iter()
+                .map(|(key, direction)| (*key, direction.reverse()))
+                .collect(),
+            completed_last: self.completed_last,
+        }
+    }
+    // Adds a key breaking the ties of the current ones, in its default direction
+    pub fn push_key(&mut self, key: SortKey) {
+        self.keys.retain(|(k, _)| *k != key);
+        self.keys.push((key, key.default_direction()));
+    }
+    pub fn toggle_completed_last(&mut self) {
+        self.completed_last = !self.completed_last
+    }
+}
+// Written in the configuration syntax
+impl std::fmt::Display for SortingMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut items: Vec<String> = self
+            .keys
+            .iter()
+            .map(|(key, direction)| match direction {
+                SortDirection::Asc => format!("{} asc", key),
+                SortDirection::Desc => format!("{} desc", key),
+            })
+            .collect();
+        if !self.completed_last {
+            items.push("mixed".to_string());
+        }
+        write!(f, "{}", items.join(", "))
+    }
 }
 
+fn parse_sort_key(key: &str) -> Result<SortKey> {
+    match key {
+        "priority" => Ok(SortKey::Priority),
+        "due" => Ok(SortKey::Due),
+        "created" => Ok(SortKey::Created),
+        "title" => Ok(SortKey::Title),
+        "progress" => Ok(SortKey::Progress),
+        _ => bail!("Unknown sort key '{}'!", key),
+    }
+}
+
+fn parse_sort_direction(direction: &str) -> Result<SortDirection> {
+    match direction {
+        "asc" => Ok(SortDirection::Asc),
+        "desc" => Ok(SortDirection::Desc),
+        _ => bail!("Unknown sort direction '{}'!", direction),
+    }
+}
+
+// Useful enums to keep track of actions/results/events of our functions
 pub enum Action {
     ToggleRead,
     Delete,
@@ -221,19 +739,62 @@ pub enum Action {
     DecreasePriority,
     Reload,
     Sort(SortingMethod),
+    ThenSort(SortKey),
+    ReverseSort,
+    ToggleCompletedLast,
     IncreaseProgress,
     DecreaseProgress,
     Edit,
     Add,
     Export,
+    Import,
     DeleteCompleted,
+    FilterTag,
+    SwitchList,
+    MoveToList,
+    EditChecklist,
+    EditNotes,
+    ShowDetails,
+    Filter,
+    SelectView,
+    BrowseArchive,
+    Undo,
+    Redo,
+    ToggleScheduled,
+    ToggleToday,
+}
+impl Action {
+    // Actions handled without prompt nor message, the list is redrawn in place
+    pub fn is_in_place(&self) -> bool {
+        matches!(
+            self,
+            Action::ToggleRead
+                | Action::IncreasePriority
+                | Action::DecreasePriority
+                | Action::IncreaseProgress
+                | Action::DecreaseProgress
+                | Action::ToggleScheduled
+                | Action::ToggleToday
+        )
+    }
 }
 
+// Moves of the selection by a page or to an end of the list
+#[derive(Clone, Copy, PartialEq)]
+pub enum PageMove {
+    Up,
+    Down,
+    First,
+    Last,
+}
+
+#[derive(Clone, Copy, PartialEq)]
 pub enum KeyEvent {
     Back,
     Sort,
     NavigateUp,
     NavigateDown,
+    Page(PageMove),
     ToggleRead,
     Delete,
     IncreasePriority,
@@ -243,5 +804,105 @@ pub enum KeyEvent {
     Edit,
     Add,
     Export,
+    Import,
     DeleteCompleted,
+    FilterTag,
+    SwitchList,
+    MoveToList,
+    EditChecklist,
+    EditNotes,
+    ShowDetails,
+    Search,
+    NextMatch,
+    PreviousMatch,
+    ClearSearch,
+    Filter,
+    SelectView,
+    BrowseArchive,
+    Undo,
+    Redo,
+    ToggleScheduled,
+    ToggleToday,
+}
+
+// Events of the sort menu
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortEvent {
+    By(SortKey),
+    ThenBy(SortKey),
+    Reverse,
+    ToggleCompletedLast,
+    Back,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_tags() {
+        assert_eq!(
+            extract_tags("Write  the report #Work #urgent #work"),
+            (
+                "Write  the report".to_string(),
+                vec!["work".to_string(), "urgent".to_string()]
+            )
+        );
+        assert_eq!(
+            extract_tags("#home Fix issue #42, see #"),
+            ("Fix issue #42, see #".to_string(), vec!["home".to_string()])
+        );
+        assert_eq!(
+            extract_tags("Call Bob #phone-call today"),
+            ("Call Bob today".to_string(), vec!["phone-call".to_string()])
+        );
+    }
+
+    #[test]
+    fn parses_sort_keys() {
+        let sorting = SortingMethod::parse("priority").unwrap();
+        assert_eq!(
+            sorting.get_keys(),
+            &[(SortKey::Priority, SortDirection::Desc)]
+        );
+        assert!(sorting.is_completed_last());
+        for (name, key, direction) in [
+            ("due", SortKey::Due, SortDirection::Asc),
+            ("created", SortKey::Created, SortDirection::Desc),
+            ("title", SortKey::Title, SortDirection::Asc),
+            ("progress", SortKey::Progress, SortDirection::Desc),
+        ] {
+            assert_eq!(
+                SortingMethod::parse(name).unwrap().get_keys(),
+                &[(key, direction)]
+            );
+        }
+    }
+
+    #[test]
+    fn parses_sort_chains() {
+        let sorting = SortingMethod::parse("Priority desc, due asc,title ASC, mixed").unwrap();
+        assert_eq!(
+            sorting.get_keys(),
+            &[
+                (SortKey::Priority, SortDirection::Desc),
+                (SortKey::Due, SortDirection::Asc),
+                (SortKey::Title, SortDirection::Asc)
+            ]
+        );
+        assert!(!sorting.is_completed_last());
+        assert_eq!(
+            SortingMethod::parse("due desc").unwrap().get_keys(),
+            &[(SortKey::Due, SortDirection::Desc)]
+        );
+    }
+
+    #[test]
+    fn reports_sort_errors() {
+        assert!(SortingMethod::parse("").is_err());
+        assert!(SortingMethod::parse("mixed").is_err());
+        assert!(SortingMethod::parse("urgency").is_err());
+        assert!(SortingMethod::parse("due up").is_err());
+        assert!(SortingMethod::parse("due asc desc").is_err());
+    }
 }
@@ -0,0 +1,347 @@
+use crate::{
+    date_utils::format_date,
+    model::{MyDateTime, Todo},
+};
+use chrono::{Datelike, Duration, NaiveDate};
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+
+// Granularity of the rows of the report
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ReportPeriod {
+    Day,
+    Week,
+}
+impl ReportPeriod {
+    // First day of the period containing the date, weeks start on monday
+    fn get_start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            ReportPeriod::Day => date,
+            ReportPeriod::Week => {
+                date - Duration::days(date.weekday().num_days_from_monday() as i64)
+            }
+        }
+    }
+    fn get_label(&self, start: NaiveDate) -> String {
+        match self {
+            ReportPeriod::Day => format_date(&start),
+            ReportPeriod::Week => start.format("%G-W%V").to_string(),
+        }
+    }
+}
+
+// Statistics on the TODOs completed and the progress changes during a period
+#[derive(Default)]
+pub struct PeriodStats {
+    completed: usize,
+    overdue: usize,
+    lead_time_days: f64,
+    progress_changes: usize,
+}
+impl PeriodStats {
+    fn add_completed(&mut self, todo: &Todo, completed_at: &MyDateTime) {
+        let MyDateTime(completed_at) = completed_at;
+        self.completed += 1;
+        if todo
+            .get_due_datetime()
+            .is_some_and(|due| due < *completed_at)
+        {
+            self.overdue += 1;
+        }
+        let lead_time = *completed_at - todo.get_created_date().get_0();
+        self.lead_time_days += lead_time.num_seconds() as f64 / 86400.0;
+    }
+    // Average number of days between the creation and the completion
+    fn get_average_lead_time(&self) -> Option<f64> {
+        match self.completed {
+            0 => None,
+            n => Some(self.lead_time_days / n as f64),
+        }
+    }
+    fn get_cells(&self) -> [String; 4] {
+        [
+            self.completed.to_string(),
+            self.overdue.to_string(),
+            self.get_average_lead_time()
+                .map_or("-".to_string(), |days| format!("{:.1} d", days)),
+            self.progress_changes.to_string(),
+        ]
+    }
+}
+
+const COLUMNS: [&str; 5] = [
+    "Period",
+    "Completed",
+    "Overdue",
+    "Avg lead time",
+    "Progress changes",
+];
+
+// Completion history of a set of TODOs since a given date
+pub struct Report {
+    since: Option<NaiveDate>,
+    rows: Vec<(String, PeriodStats)>,
+    total: PeriodStats,
+    undated: usize,
+}
+impl Report {
+    // Groups the completions and progress changes by period
+    // Completed TODOs without completion date (completed or imported with an older version) cannot be placed
+    pub fn build(todos: &[Todo], since: Option<NaiveDate>, period: ReportPeriod) -> Report {
+        let is_after =
+            |MyDateTime(date): &MyDateTime| since.is_none_or(|since| date.date() >= since);
+        let mut periods: BTreeMap<NaiveDate, PeriodStats> = BTreeMap::new();
+        let mut total = PeriodStats::default();
+        let mut undated = 0;
+        for todo in todos {
+            match (todo.is_complete(), todo.get_completed_date()) {
+                (true, Some(completed_at)) if is_after(completed_at) => {
+                    let start = period.get_start(completed_at.get_0().date());
+                    periods
+                        .entry(start)
+                        .or_default()
+                        .add_completed(todo, completed_at);
+                    total.add_completed(todo, completed_at);
+                }
+                (true, None) => undated += 1,
+                _ => (),
+            }
+            for change in todo.get_progress_log() {
+                if is_after(change.get_date()) {
+                    let start = period.get_start(change.get_date().get_0().date());
+                    periods.entry(start).or_default().progress_changes += 1;
+                    total.progress_changes += 1;
+                }
+            }
+        }
+        Report {
+            since,
+            rows: periods
+                .into_iter()
+                .map(|(start, stats)| (period.get_label(start), stats))
+                .collect(),
+            total,
+            undated,
+        }
+    }
+    fn get_title(&self) -> String {
+        match self.since {
+            Some(since) => format!("Completion report since {}", format_date(&since)),
+            None => "Completion report".to_string(),
+        }
+    }
+    fn get_lines(&self) -> Vec<[String; 5]> {
+        let mut lines: Vec<[String; 5]> = self
+            .rows
+            .iter()
+            .map(|(label, stats)| {
+                let [a, b, c, d] = stats.get_cells();
+                [label.clone(), a, b, c, d]
+            })
+            .collect();
+        let [a, b, c, d] = self.total.get_cells();
+        lines.push(["Total".to_string(), a, b, c, d]);
+        lines
+    }
+    fn get_undated_note(&self) -> Option<String> {
+        match self.undated {
+            0 => None,
+            n => Some(format!(
+                "{} completed TODOs without completion date are not counted",
+                n
+            )),
+        }
+    }
+    // Aligned plain text table
+    pub fn to_table(&self) -> Vec<String> {
+        let lines = self.get_lines();
+        let widths: Vec<usize> = (0..COLUMNS.len())
+            .map(|col| {
+                lines
+                    .iter()
+                    .map(|line| line[col].chars().count())
+                    .chain([COLUMNS[col].len()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let format_line = |cells: &[&str]| {
+            cells
+                .iter()
+                .enumerate()
+                .map(|(col, cell)| match col {
+                    0 => format!("{:<width$}", cell, width = widths[col]),
+                    _ => format!("{:>width$}", cell, width = widths[col]),
+                })
+                .collect::<Vec<String>>()
+                .join("  ")
+        };
+        let mut table = vec![self.get_title(), format_line(&COLUMNS)];
+        table.extend(lines.iter().map(|line| {
+            format_line(&line.iter().map(|cell| cell.as_str()).collect::<Vec<&str>>())
+        }));
+        table.extend(self.get_undated_note());
+        table
+    }
+    // Markdown document with the same table
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# {}\n\n", self.get_title());
+        markdown.push_str(&format!("| {} |\n", COLUMNS.join(" | ")));
+        markdown.push_str(&format!("|{}\n", "---|".repeat(COLUMNS.len())));
+        for line in self.get_lines() {
+            markdown.push_str(&format!("| {} |\n", line.join(" | ")));
+        }
+        if let Some(note) = self.get_undated_note() {
+            markdown.push_str(&format!("\n{}\n", note));
+        }
+        markdown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ProgressChange;
+    use humphrey_json::{prelude::*, Value};
+
+    fn datetime(year: i32, month: u32, day: u32, hour: u32) -> MyDateTime {
+        MyDateTime(
+            NaiveDate::from_ymd_opt(year, month, day)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap(),
+        )
+    }
+
+    // TODO created and completed at the given times
+    fn completed(due: Option<&str>, created: MyDateTime, completed_at: MyDateTime) -> Todo {
+        let mut todo = Todo::new("Write report", 0, due);
+        todo.set_creation_date(created);
+        todo.set_completed(true);
+        todo.set_completed_date(Some(completed_at));
+        todo
+    }
+
+    fn progress_change(date: &str) -> ProgressChange {
+        let value = Value::parse(format!(r#"{{"date": "{}", "progress": "Half"}}"#, date));
+        ProgressChange::from_json(&value.unwrap()).unwrap()
+    }
+
+    fn get_rows(report: &Report) -> Vec<(String, usize)> {
+        report
+            .rows
+            .iter()
+            .map(|(label, stats)| (label.clone(), stats.completed))
+            .collect()
+    }
+
+    #[test]
+    fn groups_completions_by_day() {
+        let todos = [
+            completed(None, datetime(2026, 10, 1, 9), datetime(2026, 10, 2, 9)),
+            completed(None, datetime(2026, 10, 1, 9), datetime(2026, 10, 2, 21)),
+            completed(None, datetime(2026, 10, 1, 9), datetime(2026, 10, 5, 9)),
+        ];
+        let report = Report::build(&todos, None, ReportPeriod::Day);
+        let label = |day| format_date(&NaiveDate::from_ymd_opt(2026, 10, day).unwrap());
+        assert_eq!(get_rows(&report), [(label(2), 2), (label(5), 1)]);
+        assert_eq!(report.total.completed, 3);
+    }
+
+    #[test]
+    fn groups_completions_by_iso_week() {
+        let todos = [
+            // Monday and Sunday of the same week
+            completed(None, datetime(2026, 10, 1, 9), datetime(2026, 10, 12, 9)),
+            completed(None, datetime(2026, 10, 1, 9), datetime(2026, 10, 18, 23)),
+            // The first ISO week of 2026 starts on Monday 29-12-2025
+            completed(None, datetime(2025, 12, 1, 9), datetime(2025, 12, 29, 9)),
+            completed(None, datetime(2025, 12, 1, 9), datetime(2026, 1, 4, 9)),
+            // 2026 has 53 ISO weeks, the last one ends on Sunday 03-01-2027
+            completed(None, datetime(2026, 12, 1, 9), datetime(2027, 1, 3, 9)),
+        ];
+        let report = Report::build(&todos, None, ReportPeriod::Week);
+        assert_eq!(
+            get_rows(&report),
+            [
+                ("2026-W01".to_string(), 2),
+                ("2026-W42".to_string(), 2),
+                ("2026-W53".to_string(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_overdue_completions_and_lead_time() {
+        let todos = [
+            // Due at the end of the day: completed on time
+            completed(
+                Some("2026-10-02"),
+                datetime(2026, 10, 1, 22),
+                datetime(2026, 10, 2, 22),
+            ),
+            // Due at 14:00, completed at 15:00
+            completed(
+                Some("2026-10-02 14:00"),
+                datetime(2026, 9, 30, 15),
+                datetime(2026, 10, 2, 15),
+            ),
+            completed(
+                Some("2026-10-01"),
+                datetime(2026, 9, 29, 12),
+                datetime(2026, 10, 2, 12),
+            ),
+        ];
+        let report = Report::build(&todos, None, ReportPeriod::Day);
+        assert_eq!(report.total.completed, 3);
+        assert_eq!(report.total.overdue, 2);
+        // 1, 2 and 3 days
+        assert_eq!(report.total.get_average_lead_time(), Some(2.0));
+        assert_eq!(report.total.get_cells()[2], "2.0 d");
+        assert_eq!(PeriodStats::default().get_cells()[2], "-");
+    }
+
+    #[test]
+    fn filters_since_a_date() {
+        let since = NaiveDate::from_ymd_opt(2026, 10, 10);
+        let mut old = completed(None, datetime(2026, 10, 1, 9), datetime(2026, 10, 9, 23));
+        old.set_progress_log(vec![
+            progress_change("2026-10-05T10:00:00"),
+            progress_change("2026-10-10T00:00:00"),
+        ]);
+        let mut open = Todo::new("Call the bank", 0, None);
+        open.set_progress_log(vec![
+            progress_change("2026-10-09T10:00:00"),
+            progress_change("2026-10-11T10:00:00"),
+            progress_change("2026-10-11T12:00:00"),
+        ]);
+        let recent = completed(None, datetime(2026, 10, 1, 9), datetime(2026, 10, 10, 0));
+        let report = Report::build(&[old, open, recent], since, ReportPeriod::Day);
+        assert_eq!(report.total.completed, 1);
+        assert_eq!(report.total.progress_changes, 3);
+        let label = |day| format_date(&NaiveDate::from_ymd_opt(2026, 10, day).unwrap());
+        assert_eq!(get_rows(&report), [(label(10), 1), (label(11), 0)]);
+        assert_eq!(report.rows[1].1.progress_changes, 2);
+    }
+
+    #[test]
+    fn notes_undated_completions() {
+        let mut undated = Todo::new("Write report", 0, None);
+        undated.set_completed(true);
+        undated.set_completed_date(None);
+        let todos = [
+            undated,
+            completed(None, datetime(2026, 10, 1, 9), datetime(2026, 10, 2, 9)),
+        ];
+        let report = Report::build(&todos, None, ReportPeriod::Day);
+        assert_eq!(report.total.completed, 1);
+        let note = "1 completed TODOs without completion date are not counted";
+        assert_eq!(
+            report.to_table().last().map(|line| line.as_str()),
+            Some(note)
+        );
+        assert!(report.to_markdown().ends_with(&format!("\n{}\n", note)));
+        let report = Report::build(&todos[1..], None, ReportPeriod::Day);
+        assert!(report.get_undated_note().is_none());
+    }
+}
@@ -1,10 +1,19 @@
 use crate::{
-    get_completed_todos, io,
-    md_utils::export_to_md,
-    model::{Action, MyDate, SortingMethod, Todo, TodoCollection},
+    date_utils::convert_str_valid_date,
+    filter::Filter,
+    history::{self, Snapshot},
+    io,
+    keymap::Keymap,
+    md_utils::{export_to_md, import_from_md, ImportError},
+    model::{
+        extract_tags, format_tags, Action, MyDate, MyDateTime, SortDirection, SortKey,
+        SortingMethod, Todo, TodoCollection,
+    },
     storage,
+    view::{self, View},
 };
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::{Duration, Local};
 use std::cmp::Ordering::{self, Equal};
 use std::env;
 
@@ -16,43 +25,140 @@ pub fn get_todo_tuple(todos_tup: Vec<(String, Todo)>) -> TodoCollection {
 
 // This function is the main TODO listing screen
 // Handles various actions on selected individual TODO elements
+// The optional view replaces the filter and sorting method on startup
+// The full-screen mode shows the details of the selected TODO next to the list and edits TODOs inline
 pub fn navigate_todos(
     db: &mut storage::DatabaseModel,
     start_position: usize,
     mut sorting_method: SortingMethod,
+    list: &str,
+    view: Option<&View>,
+    full_screen: bool,
+    keymap: &Keymap,
 ) -> Result<()> {
     let mut pos = start_position; // Starting position of the arrow
+    let mut tag_filter: Option<String> = None; // Only TODOs with this tag are shown
+    let mut search = String::new(); // Only TODOs matching this text are shown
+
+    // Last frame drawn, only the changed lines are redrawn
+    let mut screen = match full_screen {
+        true => io::Screen::full_screen()?,
+        false => io::Screen::default(),
+    };
+    let mut filter: Option<Filter> = None; // Only TODOs matching this filter are shown
+    let mut list = list.to_string(); // Name of the list backing the DB
+    let mut view_name: Option<String> = None; // Name of the active view
+    let mut show_scheduled = false; // TODOs with a future start date are shown
+    let mut today_only = false; // Only TODOs to work on today are shown
+    if let Some(view) = view {
+        apply_view(view, &mut filter, &mut sorting_method);
+        view_name = Some(view.get_name().to_string());
+    }
+    let mut todos = TodoCollection::new(); // TODOs shown, kept in sync with the DB by the actions
+    let mut hidden = 0; // Number of TODOs scheduled later
+    let mut reload = true; // The TODOs are read from the DB again after actions changing other TODOs
     loop {
-        let todos_db = storage::get_todos(db)?; // Gets TODOs from DB
-        let mut todos = get_todo_tuple(todos_db);
-        // Sorts the TODO collection
-        match sorting_method {
-            SortingMethod::Priority => sort_todos_by_priority_desc(&mut todos),
-            SortingMethod::Due => sort_todos_by_due_date_asc(&mut todos),
-            SortingMethod::Created => sort_todos_by_created_date_asc(&mut todos),
+        if reload {
+            // Gets TODOs from DB
+            let todos_db = match &filter {
+                Some(filter) => storage::get_filtered_todos(db, filter)?,
+                None => storage::get_todos(db)?,
+            };
+            todos = get_todo_tuple(todos_db);
+            if let Some(tag) = &tag_filter {
+                todos.retain(|todo| todo.has_tag(tag));
+            }
+            if today_only {
+                todos.retain(is_for_today);
+            }
+            let count = todos.len();
+            if !show_scheduled {
+                todos.retain(|todo| !todo.is_scheduled_later());
+            }
+            hidden = count - todos.len();
         }
+        // A TODO changed in memory may not match the filters anymore
+        todos.retain(|todo| {
+            filter.as_ref().is_none_or(|filter| filter.matches(todo))
+                && tag_filter.as_ref().is_none_or(|tag| todo.has_tag(tag))
+                && (!today_only || is_for_today(todo))
+        });
+        sort_todos(&mut todos, &sorting_method); // Sorts the TODO collection
+
         // Reads action from user
-        let navigation = io::screen_navigate_todos(&mut todos, pos)?;
+        let header = get_header(
+            &list,
+            tag_filter.as_deref(),
+            view_name.as_deref(),
+            filter.as_ref(),
+            &sorting_method,
+            today_only,
+            hidden,
+        );
+        let navigation = io::screen_navigate_todos(
+            &todos,
+            pos,
+            header.as_deref(),
+            &mut search,
+            &mut screen,
+            keymap,
+        )?;
         if let Some((p, action)) = navigation {
             pos = p; // Update the position variable to where the use executed the action
+
+            // Only the selected TODO is tracked when the action cannot change the others
+            let todo_snapshot = todos
+                .get(p)
+                .filter(|todo| changes_one_todo(&action, todo))
+                .map(|todo| Snapshot::take_todo(&list, todo));
+            // Actions which may change other TODOs than the selected one, or the filters, read the DB again
+            reload = !matches!(
+                action,
+                Action::Sort(_)
+                    | Action::ThenSort(_)
+                    | Action::ReverseSort
+                    | Action::ToggleCompletedLast
+                    | Action::Export
+                    | Action::ShowDetails
+                    | Action::Reload
+            ) && todo_snapshot.is_none();
+            // The changes made by the action are recorded in the history to be undone,
+            // the actions only changing what is shown are not
+            let mut snapshot = match action {
+                Action::Undo
+                | Action::Redo
+                | Action::SwitchList
+                | Action::Filter
+                | Action::FilterTag
+                | Action::SelectView
+                | Action::ToggleScheduled
+                | Action::ToggleToday => None,
+                _ if !reload => None,
+                _ => Some(Snapshot::take(db, &list)?),
+            };
             match action {
                 // Each action calls the correct DB action
+                Action::Add if screen.is_full_screen() => add_todo_inline(db, &mut screen)?,
                 Action::Add => {
                     io::show_cursor()?;
                     io::clear_term()?;
                     add_todo(db)?;
                 }
+                Action::Edit if screen.is_full_screen() => {
+                    if !todos.is_empty() {
+                        edit_todo_inline(db, &mut screen, &mut todos[p])?;
+                    }
+                }
                 Action::Edit => {
                     io::show_cursor()?;
                     io::clear_term()?;
                     if !todos.is_empty() {
-                        edit_todo(db, &todos[p])?;
+                        edit_todo(db, &mut todos[p])?;
                     }
                 }
                 Action::ToggleRead => {
                     if !todos.is_empty() {
-                        todos[p].toggle_read(); //Mark TODO read
-                        storage::update_todo(db, &todos[p])?;
+                        toggle_todo(db, &mut todos[p])?; //Mark TODO read
                     }
                 }
                 Action::IncreasePriority => {
@@ -82,19 +188,148 @@ pub fn navigate_todos(
                 Action::Delete => {
                     if !todos.is_empty() {
                         storage::delete_todo(db, &todos[p])?;
+                        todos.remove(p);
                     }
                 }
                 Action::DeleteCompleted => {
                     if !todos.is_empty() {
-                        delete_completed(db)?;
+                        if let Some(snapshot) = snapshot.as_mut() {
+                            snapshot.add_archive(&list)?;
+                        }
+                        archive_completed(db, &list)?;
                     }
                 }
+                Action::BrowseArchive => {
+                    if let Some(snapshot) = snapshot.as_mut() {
+                        snapshot.add_archive(&list)?;
+                    }
+                    browse_archive(db, &list)?;
+                }
                 Action::Sort(new_sort_method) => {
                     sorting_method = new_sort_method;
                 }
-                Action::Export => export_to_md(&todos)?,
+                Action::ThenSort(key) => sorting_method.push_key(key),
+                Action::ReverseSort => sorting_method = sorting_method.reversed(),
+                Action::ToggleCompletedLast => sorting_method.toggle_completed_last(),
+                Action::ToggleScheduled => show_scheduled = !show_scheduled,
+                Action::ToggleToday => today_only = !today_only,
+                Action::Export => export_all_todos(db, &sorting_method, &list)?,
+                Action::FilterTag => {
+                    io::show_cursor()?;
+                    io::clear_term()?;
+                    let tags = get_all_tags(db)?;
+                    tag_filter = io::input_tag_filter(&tags, tag_filter.as_deref())?;
+                }
+                Action::Filter => {
+                    io::show_cursor()?;
+                    io::clear_term()?;
+                    filter = io::input_filter(filter.as_ref())?;
+                    view_name = None;
+                }
+                Action::SelectView => {
+                    io::show_cursor()?;
+                    io::clear_term()?;
+                    match view::get_views() {
+                        Ok(views) if views.is_empty() => {
+                            io::confirm_message(
+                                "No view defined, add VIEW_<NAME>=\"<filter> | <sort>\" to the configuration file",
+                            )?;
+                            io::wait_any_key()?;
+                        }
+                        Ok(views) => match io::input_view(&views, view_name.as_deref())? {
+                            Some(idx) => {
+                                apply_view(&views[idx], &mut filter, &mut sorting_method);
+                                view_name = Some(views[idx].get_name().to_string());
+                                pos = 0;
+                            }
+                            None => {
+                                filter = None;
+                                view_name = None;
+                            }
+                        },
+                        Err(e) => {
+                            io::confirm_message(&format!("{:#}", e))?;
+                            io::wait_any_key()?;
+                        }
+                    }
+                }
+                Action::SwitchList => {
+                    io::show_cursor()?;
+                    io::clear_term()?;
+                    if let Some(new_list) = io::input_list(&storage::get_lists()?, &list)? {
+                        *db = storage::connect_db(&new_list)?;
+                        list = new_list;
+                        tag_filter = None;
+                        search.clear();
+                        pos = 0;
+                    }
+                }
+                Action::MoveToList => {
+                    io::show_cursor()?;
+                    io::clear_term()?;
+                    if !todos.is_empty() {
+                        if let Some(target) = io::input_list(&storage::get_lists()?, &list)? {
+                            if let Some(snapshot) = snapshot.as_mut() {
+                                snapshot.add_list(&target)?;
+                            }
+                            move_todo(db, &todos[p], &list, &target)?;
+                        }
+                    }
+                }
+                Action::EditChecklist => {
+                    io::show_cursor()?;
+                    if !todos.is_empty() && io::edit_checklist(&mut todos[p])? {
+                        storage::update_todo(db, &todos[p])?;
+                    }
+                }
+                Action::EditNotes => {
+                    io::show_cursor()?;
+                    io::clear_term()?;
+                    if !todos.is_empty() {
+                        let notes = io::input_notes(todos[p].get_notes())?;
+                        todos[p].set_notes(&notes);
+                        storage::update_todo(db, &todos[p])?;
+                    }
+                }
+                Action::ShowDetails => {
+                    io::clear_term()?;
+                    if !todos.is_empty() {
+                        io::write_todo_details(&todos[p])?;
+                        io::wait_any_key()?;
+                    }
+                }
+                Action::Import => {
+                    io::clear_term()?;
+                    let (count, errors) = import_todos(db, &list)?;
+                    io::write_import_report(count, &errors)?;
+                    io::wait_any_key()?;
+                }
+                Action::Undo => {
+                    screen.clear_menu()?;
+                    match history::undo(db, &list)? {
+                        Some(description) => {
+                            io::flash_message(&format!("Undone: {}", description))?
+                        }
+                        None => io::flash_message("Nothing to undo")?,
+                    }
+                }
+                Action::Redo => {
+                    screen.clear_menu()?;
+                    match history::redo(db, &list)? {
+                        Some(description) => {
+                            io::flash_message(&format!("Redone: {}", description))?
+                        }
+                        None => io::flash_message("Nothing to redo")?,
+                    }
+                }
                 Action::Reload => (),
             }
+            if let Some(snapshot) = snapshot {
+                history::record(snapshot.diff(db)?)?;
+            }
+            if let Some(snapshot) = todo_snapshot {
+                history::record(snapshot.diff_todo(&todos))?;
+            }
             continue;
         }
         // If the navigation is None, this means exit the loop
@@ -104,35 +339,279 @@ pub fn navigate_todos(
             .parse::<bool>()
             .unwrap_or(false)
         {
-            export_to_md(&todos)?;
+            export_all_todos(db, &sorting_method, &list)?;
         }
         break;
     }
     Ok(())
 }
 
-// Deletes all completed TODOs
-pub fn delete_completed(db: &mut storage::DatabaseModel) -> Result<()> {
-    let todos_db = get_completed_todos(db)?; // Loads all completed TODOs
-    let todos = get_todo_tuple(todos_db); // Extract the TodoCollection
-    for todo in todos {
-        storage::delete_todo(db, &todo)? // Delete each todo
+// Whether an action only changes the given TODO, so that the TODOs in memory stay in sync with the DB
+// Completing a recurring TODO also inserts its next occurrence
+fn changes_one_todo(action: &Action, todo: &Todo) -> bool {
+    match action {
+        Action::ToggleRead => todo.is_complete() || todo.get_recurrence().is_none(),
+        Action::IncreasePriority
+        | Action::DecreasePriority
+        | Action::IncreaseProgress
+        | Action::DecreaseProgress
+        | Action::EditChecklist
+        | Action::EditNotes
+        | Action::Delete => true,
+        _ => false,
+    }
+}
+
+// Builds the header shown above the list from the active list and filters
+fn get_header(
+    list: &str,
+    tag_filter: Option<&str>,
+    view_name: Option<&str>,
+    filter: Option<&Filter>,
+    sorting_method: &SortingMethod,
+    today_only: bool,
+    hidden: usize,
+) -> Option<String> {
+    let mut parts = Vec::new();
+    if list != storage::DEFAULT_LIST {
+        parts.push(format!("List: {}", list));
+    }
+    if today_only {
+        parts.push("Today".to_string());
+    }
+    if let Some(tag) = tag_filter {
+        parts.push(format!("Tag: #{}", tag));
+    }
+    // The filter of the active view is not repeated
+    match (view_name, filter) {
+        (Some(name), _) => parts.push(format!("View: {}", name)),
+        (None, Some(filter)) => parts.push(format!("Filter: {}", filter)),
+        (None, None) => (),
+    }
+    // The sorting method is only shown when it differs from the configured one
+    let sort = sorting_method.to_string();
+    if get_default_sort().ok().map(|default| default.to_string()) != Some(sort.clone()) {
+        parts.push(format!("Sort: {}", sort));
+    }
+    if hidden > 0 {
+        parts.push(format!("{} scheduled later (h to show)", hidden));
+    }
+    match parts.is_empty() {
+        true => None,
+        false => Some(parts.join(" | ")),
+    }
+}
+
+// TODOs to work on today: incomplete and scheduled for today, due today or overdue
+pub fn is_for_today(todo: &Todo) -> bool {
+    let today = Local::now().date_naive();
+    let scheduled_today = todo
+        .get_scheduled_date()
+        .as_ref()
+        .is_some_and(|MyDate(date)| *date == today);
+    // The whole day counts, whatever the due time and DUE_WARNING_MINUTES
+    let due_today = todo
+        .get_due_datetime()
+        .is_some_and(|due| due.date() <= today);
+    !todo.is_complete() && (scheduled_today || due_today)
+}
+
+// Replaces the filter with the one of the view, and the sorting method if the view defines one
+fn apply_view(view: &View, filter: &mut Option<Filter>, sorting_method: &mut SortingMethod) {
+    *filter = view.get_filter().clone();
+    if let Some(sort) = view.get_sort() {
+        *sorting_method = sort.clone();
+    }
+}
+
+// Exports all TODOs to markdown regardless of the active filters
+fn export_all_todos(
+    db: &mut storage::DatabaseModel,
+    sorting_method: &SortingMethod,
+    list: &str,
+) -> Result<()> {
+    let mut todos = get_todo_tuple(storage::get_todos(db)?);
+    sort_todos(&mut todos, sorting_method);
+    export_to_md(&todos, list)
+}
+
+// Moves a TODO from the current list to the target list
+pub fn move_todo(
+    db: &mut storage::DatabaseModel,
+    todo: &Todo,
+    list: &str,
+    target: &str,
+) -> Result<()> {
+    if list == target {
+        return Ok(());
+    }
+    let mut target_db = storage::connect_db(target)?;
+    storage::insert_todo(&mut target_db, todo)?;
+    storage::delete_todo(db, todo)?;
+    Ok(())
+}
+
+// Lists all the tags used in the DB, sorted alphabetically
+pub fn get_all_tags(db: &mut storage::DatabaseModel) -> Result<Vec<String>> {
+    let todos = get_todo_tuple(storage::get_todos(db)?);
+    let mut tags: Vec<String> = todos
+        .iter()
+        .flat_map(|todo| todo.get_tags().iter().cloned())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    Ok(tags)
+}
+
+// Toggles the completion of a TODO, completing a recurring TODO creates its next occurrence
+pub fn toggle_todo(db: &mut storage::DatabaseModel, todo: &mut Todo) -> Result<()> {
+    todo.toggle_read();
+    if todo.is_complete() {
+        schedule_next_occurrence(db, todo)?;
+    }
+    storage::update_todo(db, todo)
+}
+
+// Inserts the next occurrence of a completed recurring TODO
+// The completed TODO stops recurring so that toggling it again does not duplicate the next one
+fn schedule_next_occurrence(db: &mut storage::DatabaseModel, todo: &mut Todo) -> Result<()> {
+    if let Some(next) = todo.get_next_occurrence() {
+        storage::insert_todo(db, &next)?;
+        todo.set_recurrence(None);
     }
     Ok(())
 }
 
+// Moves TODOs to the archive of the list
+// TODOs completed before completion dates were recorded are dated from their archiving
+fn archive_todos(db: &mut storage::DatabaseModel, list: &str, todos: Vec<Todo>) -> Result<usize> {
+    let mut archive = storage::connect_archive(list)?;
+    let count = todos.len();
+    for mut todo in todos {
+        if todo.get_completed_date().is_none() {
+            todo.set_completed_date(Some(MyDateTime(Local::now().naive_local())));
+        }
+        storage::insert_todo(&mut archive, &todo)?;
+        storage::delete_todo(db, &todo)?;
+    }
+    Ok(count)
+}
+
+// Archives all completed TODOs, returns the number of archived TODOs
+pub fn archive_completed(db: &mut storage::DatabaseModel, list: &str) -> Result<usize> {
+    let todos_db = storage::get_filtered_todos(db, &Filter::completed())?; // Loads all completed TODOs
+    archive_todos(db, list, get_todo_tuple(todos_db))
+}
+
+// Archives the TODOs completed more than AUTO_ARCHIVE_DAYS days ago, if set in the configuration
+// TODOs completed before completion dates were recorded are archived as well
+// The archiving is recorded in the history so that it can be undone
+pub fn auto_archive(db: &mut storage::DatabaseModel, list: &str) -> Result<usize> {
+    let days = match env::var("AUTO_ARCHIVE_DAYS")
+        .ok()
+        .and_then(|days| days.parse::<u32>().ok())
+    {
+        Some(days) => days,
+        None => return Ok(0),
+    };
+    let limit = Local::now().naive_local() - Duration::days(i64::from(days));
+    let todos: Vec<Todo> = get_todo_tuple(storage::get_filtered_todos(db, &Filter::completed())?)
+        .into_iter()
+        .filter(|todo| match todo.get_completed_date() {
+            Some(MyDateTime(date)) => *date < limit,
+            None => true,
+        })
+        .collect();
+    if todos.is_empty() {
+        return Ok(0);
+    }
+    let mut snapshot = Snapshot::take(db, list)?;
+    snapshot.add_archive(list)?;
+    let count = archive_todos(db, list, todos)?;
+    history::record(snapshot.diff(db)?)?;
+    Ok(count)
+}
+
+// Archive browser, the restored TODOs are moved back to the list
+fn browse_archive(db: &mut storage::DatabaseModel, list: &str) -> Result<()> {
+    let mut archive = storage::connect_archive(list)?;
+    let mut search = String::new();
+    let mut pos = 0;
+    loop {
+        let mut todos = get_todo_tuple(storage::get_todos(&mut archive)?);
+        // Most recently completed first
+        todos.sort_by(|l, r| {
+            let completed = |todo: &Todo| todo.get_completed_date().as_ref().map(|d| d.get_0());
+            completed(r).cmp(&completed(l))
+        });
+        match io::screen_archive(&todos, pos, &mut search)? {
+            Some(p) => {
+                storage::insert_todo(db, &todos[p])?;
+                storage::delete_todo(&mut archive, &todos[p])?;
+                pos = p;
+            }
+            None => return Ok(()),
+        }
+    }
+}
+
+// Merges the TODOs from the markdown file into the DB by id
+// The markdown file only stores the created date and the progress of incomplete TODOs without checklist,
+// so we keep the values from the DB when they are consistent with the file
+// Returns the number of imported TODOs and the lines which could not be parsed
+pub fn import_todos(
+    db: &mut storage::DatabaseModel,
+    list: &str,
+) -> Result<(usize, Vec<ImportError>)> {
+    let (todos, errors) = import_from_md(list)?;
+    let count = todos.len();
+    for mut todo in todos {
+        let mut was_complete = false;
+        if let Ok(existing) = storage::get_todo(db, todo.get_id()) {
+            was_complete = existing.is_complete();
+            let created = existing.get_created_date().get_0();
+            if created.date() == todo.get_created_date().get_0().date() {
+                todo.set_creation_date(MyDateTime(created));
+            }
+            // The progress bar of a TODO with a checklist is derived from the checklist
+            if todo.is_complete() || !todo.get_subtasks().is_empty() {
+                todo.set_progress(existing.get_progress().clone());
+            }
+            if todo.is_complete() && existing.is_complete() {
+                todo.set_completed_date(existing.get_completed_date().clone());
+            }
+            // The markdown file does not store the progress log and the start date
+            todo.set_progress_log(existing.get_progress_log().to_vec());
+            todo.set_scheduled_date(existing.get_scheduled_date().clone());
+            if todo.get_progress() != existing.get_progress() {
+                todo.log_progress();
+            }
+        }
+        // TODOs completed in the markdown file are dated from the import, and recurring ones are
+        // followed by their next occurrence
+        if todo.is_complete() && !was_complete {
+            todo.set_completed_date(Some(MyDateTime(Local::now().naive_local())));
+            schedule_next_occurrence(db, &mut todo)?;
+        }
+        storage::update_todo(db, &todo)?;
+    }
+    Ok((count, errors))
+}
+
 // Handles the TODO addition page
 // Returns early to menu if no title is set
 pub fn add_todo(db: &mut storage::DatabaseModel) -> Result<()> {
-    let title = io::input_title(None)?; // Prompts title
+    let title_input = io::input_title(None)?; // Prompts title
+    let (title, tags) = extract_tags(&title_input); // Extracts inline #tags
     if title.is_empty() {
         return Ok(()); // Early return
     }
-    let due_date_str = io::input_due_date(&None)?; // Prompts due date
+    let due_date_str = io::input_due_date(&None, &None, &None)?; // Prompts due date
     let priority = io::input_priority(0)?; // Prompts for priority level
 
     let due_date = convert_empty_str_option(&due_date_str); // Converts due date
-    let todo = Todo::new(&title, priority, due_date); // Create new TODO element
+    let mut todo = Todo::new(&title, priority, due_date); // Create new TODO element
+    todo.set_tags(tags);
 
     storage::insert_todo(db, &todo)?; // Add to DB
     Ok(())
@@ -140,94 +619,166 @@ pub fn add_todo(db: &mut storage::DatabaseModel) -> Result<()> {
 
 // Handles the TODO edition page
 // Returns early to menu if no title is set
-// Similar to add_todo but with presets set by the existing TODO
-pub fn edit_todo(db: &mut storage::DatabaseModel, todo: &Todo) -> Result<()> {
-    let title = io::input_title(Some(todo.get_title()))?;
+// Similar to add_todo but with presets set by the existing TODO, only the edited fields are updated
+pub fn edit_todo(db: &mut storage::DatabaseModel, todo: &mut Todo) -> Result<()> {
+    let prewrite = match todo.get_tags().is_empty() {
+        true => todo.get_title().to_string(),
+        false => format!("{} {}", todo.get_title(), format_tags(todo.get_tags())),
+    };
+    let title_input = io::input_title(Some(&prewrite))?;
+    let (title, tags) = extract_tags(&title_input);
     if title.is_empty() {
         return Ok(());
     }
-    let due_date_str = io::input_due_date(todo.get_due_date())?;
+    let due_date_str = io::input_due_date(
+        todo.get_due_date(),
+        todo.get_due_time(),
+        todo.get_recurrence(),
+    )?;
+    let scheduled_str = io::input_scheduled_date(todo.get_scheduled_date())?;
     let priority = io::input_priority(todo.get_priority() as usize)?;
+    let progress = io::input_progress(todo.get_progress())?;
+    let completed = io::input_completed(todo.is_complete())?;
+
+    todo.set_title(&title);
+    todo.set_tags(tags);
+    todo.edit_due_date(convert_empty_str_option(&due_date_str));
+    todo.set_scheduled_date(
+        convert_empty_str_option(&scheduled_str)
+            .and_then(|date| convert_str_valid_date(date).ok())
+            .map(MyDate),
+    );
+    todo.set_priority(priority);
+    todo.set_progress(progress);
+    let was_complete = todo.is_complete();
+    todo.set_completed(completed);
+    if completed && !was_complete {
+        schedule_next_occurrence(db, todo)?;
+    }
+
+    storage::update_todo(db, todo)?;
+    Ok(())
+}
 
-    let due_date = convert_empty_str_option(&due_date_str);
-    let mut todo_replace = Todo::new(&title, priority, due_date);
-    todo_replace.set_id(todo.get_id());
+// Handles the TODO addition in the form of the full-screen mode
+// Returns early to menu if the form is cancelled or no title is set
+fn add_todo_inline(db: &mut storage::DatabaseModel, screen: &mut io::Screen) -> Result<()> {
+    let mut form = io::TodoForm {
+        priority: "0".to_string(),
+        ..Default::default()
+    };
+    if !io::input_todo_form(screen, "New TODO", &mut form)? {
+        return Ok(());
+    }
+    let (title, tags) = extract_tags(&form.title);
+    if title.is_empty() {
+        return Ok(());
+    }
+    let due_date_str = form.due.trim().to_lowercase();
+    let mut todo = Todo::new(
+        &title,
+        form.get_priority(),
+        convert_empty_str_option(&due_date_str),
+    );
+    todo.set_tags(tags);
+    todo.set_scheduled_date(get_form_start(&form));
+    storage::insert_todo(db, &todo)?;
+    Ok(())
+}
 
-    storage::update_todo(db, &todo_replace)?;
+// Handles the TODO edition in the form of the full-screen mode, the title, dates and priority are edited
+// Returns early to menu if the form is cancelled or no title is set
+fn edit_todo_inline(
+    db: &mut storage::DatabaseModel,
+    screen: &mut io::Screen,
+    todo: &mut Todo,
+) -> Result<()> {
+    let mut form = io::TodoForm::from_todo(todo);
+    if !io::input_todo_form(screen, "Edit TODO", &mut form)? {
+        return Ok(());
+    }
+    let (title, tags) = extract_tags(&form.title);
+    if title.is_empty() {
+        return Ok(());
+    }
+    let due_date_str = form.due.trim().to_lowercase();
+    todo.set_title(&title);
+    todo.set_tags(tags);
+    todo.edit_due_date(convert_empty_str_option(&due_date_str));
+    todo.set_scheduled_date(get_form_start(&form));
+    todo.set_priority(form.get_priority());
+    storage::update_todo(db, todo)?;
     Ok(())
 }
 
-// Custom comparisons between TODO elements by priority
-// 1. Incomplete before all complete TODOs
-// 2. In case of equality sort by priority ordering, descending
-// 3. Defaults back to created date as a last resort
-fn sort_by_priority(l: &Todo, r: &Todo) -> Ordering {
-    let compare_complete = l.is_complete().cmp(&r.is_complete());
-    if compare_complete == Equal {
-        let compare_priority = l.get_priority().cmp(&r.get_priority()).reverse();
-        if compare_priority == Equal {
+fn get_form_start(form: &io::TodoForm) -> Option<MyDate> {
+    convert_str_valid_date(form.start.trim()).ok().map(MyDate)
+}
+
+// Custom comparison between TODO elements along the chain of sort keys
+// 1. Incomplete before all complete TODOs, unless the sorting method mixes them
+// 2. In case of equality sort by each key in turn, in its direction
+// 3. Defaults back to created date, descending, as a last resort
+fn compare_todos(l: &Todo, r: &Todo, sorting_method: &SortingMethod) -> Ordering {
+    let compare_complete = match sorting_method.is_completed_last() {
+        true => l.is_complete().cmp(&r.is_complete()),
+        false => Equal,
+    };
+    sorting_method
+        .get_keys()
+        .iter()
+        .fold(compare_complete, |ordering, (key, direction)| {
+            ordering.then_with(|| compare_by_key(l, r, key, direction))
+        })
+        .then_with(|| {
             l.get_created_date()
                 .get_0()
                 .cmp(&r.get_created_date().get_0())
                 .reverse()
-        } else {
-            compare_priority
-        }
-    } else {
-        compare_complete
-    }
-}
-
-// Custom comparisons between TODO elements by due date
-// 1. Incomplete before all complete TODOs
-// 2. In case of equality sort by due date, ascending, None go last
-// 3. If no due dates are set, default back to created date
-fn sort_by_due_date(l: &Todo, r: &Todo) -> Ordering {
-    let compare_complete = l.is_complete().cmp(&r.is_complete());
-    if compare_complete == Equal {
-        let ld_o = l.get_due_date();
-        let rd_o = r.get_due_date();
-        match (ld_o, rd_o) {
-            (Some(MyDate(ld)), Some(MyDate(rd))) => ld.cmp(rd),
-            (Some(_), None) => Ordering::Less,
-            (None, Some(_)) => Ordering::Greater,
-            (None, None) => l
-                .get_created_date()
-                .get_0()
-                .cmp(&r.get_created_date().get_0())
-                .reverse(),
-        }
-    } else {
-        compare_complete
-    }
+        })
 }
 
-// Custom comparisons between TODO elements by created date
-// 1. Incomplete before all complete TODOs
-// 2. In case of equality sort by created date, descending
-fn sort_by_created_date(l: &Todo, r: &Todo) -> Ordering {
-    let compare_complete = l.is_complete().cmp(&r.is_complete());
-    if compare_complete == Equal {
-        l.get_created_date()
+// Compares two TODOs on a single key, TODOs without due date go last in both directions
+fn compare_by_key(l: &Todo, r: &Todo, key: &SortKey, direction: &SortDirection) -> Ordering {
+    let ordering = match key {
+        SortKey::Priority => l.get_priority().cmp(&r.get_priority()),
+        SortKey::Due => match (l.get_due_datetime(), r.get_due_datetime()) {
+            (Some(ld), Some(rd)) => ld.cmp(&rd),
+            (Some(_), None) => return Ordering::Less,
+            (None, Some(_)) => return Ordering::Greater,
+            (None, None) => Equal,
+        },
+        SortKey::Created => l
+            .get_created_date()
             .get_0()
-            .cmp(&r.get_created_date().get_0())
-            .reverse()
-    } else {
-        compare_complete
+            .cmp(&r.get_created_date().get_0()),
+        SortKey::Title => l
+            .get_title()
+            .to_lowercase()
+            .cmp(&r.get_title().to_lowercase()),
+        SortKey::Progress => {
+            (l.get_progress().clone() as u32).cmp(&(r.get_progress().clone() as u32))
+        }
+    };
+    match direction {
+        SortDirection::Asc => ordering,
+        SortDirection::Desc => ordering.reverse(),
     }
 }
 
-// Functions to sort TODO collections by applying the appropriate sorting function
-pub fn sort_todos_by_priority_desc(todos: &mut [Todo]) {
-    todos.sort_by(sort_by_priority)
-}
-
-pub fn sort_todos_by_due_date_asc(todos: &mut [Todo]) {
-    todos.sort_by(sort_by_due_date)
+// Reads the default sorting method from the configuration, sorts by due date if it is not set
+pub fn get_default_sort() -> Result<SortingMethod> {
+    match env::var("DEFAULT_SORT") {
+        Ok(sort) if !sort.trim().is_empty() => {
+            SortingMethod::parse(&sort).with_context(|| "Invalid DEFAULT_SORT!")
+        }
+        _ => Ok(SortingMethod::by_key(SortKey::Due)),
+    }
 }
 
-pub fn sort_todos_by_created_date_asc(todos: &mut [Todo]) {
-    todos.sort_by(sort_by_created_date)
+// Sorts a TODO collection with the given sorting method
+pub fn sort_todos(todos: &mut [Todo], sorting_method: &SortingMethod) {
+    todos.sort_by(|l, r| compare_todos(l, r, sorting_method))
 }
 
 // Wraps string with Some, None if the string is empty
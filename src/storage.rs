@@ -1,20 +1,33 @@
-use crate::model::Todo;
+use crate::{filter::Filter, migration::migrate_database, model::Todo};
 use anyhow::{bail, Context, Result};
 use directories::ProjectDirs;
-use dotenv;
 use jasondb::*;
 use std::path::PathBuf;
 
 pub type DatabaseModel = Database<Todo>;
 const APPNAME: &str = "rustdo";
+// Name of the list stored in rustdo_db.json, other lists are stored in rustdo_db_<name>.json
+pub const DEFAULT_LIST: &str = "default";
+const DB_PREFIX: &str = "rustdo_db";
+// Completed TODOs are archived in rustdo_archive.json, or rustdo_archive_<name>.json for other lists
+const ARCHIVE_PREFIX: &str = "rustdo_archive";
+// Journal of the operations of all lists, used to undo them
+const HISTORY_FILENAME: &str = "rustdo_history.json";
 
 const DEFAULT_CONFIG: &str = r#"MD_FILE="$HOME/rustdo.md"
 EXPORT_ON_EXIT=false
 DEFAULT_SORT="due"
+DATE_FORMAT="dd-mm"
+RELATIVE_DATES=false
+DUE_WARNING_MINUTES=60
+FULL_SCREEN=false
+KEYMAP="default"
+THEME="default"
+VIEW_TODAY="due<=today !done | priority"
 "#;
 
-// Loads environment variables from config file
-pub fn load_env() -> Result<()> {
+// Location of the config file, written with the default configuration if it does not exist
+fn get_config_filename() -> Result<PathBuf> {
     // Locates the configuration folder
     let path_root = match ProjectDirs::from("", "", APPNAME) {
         Some(proj_dirs) => proj_dirs.config_dir().to_path_buf(),
@@ -25,27 +38,163 @@ pub fn load_env() -> Result<()> {
     if !config_filename.exists() {
         std::fs::write(&config_filename, DEFAULT_CONFIG)?
     }
-    dotenv::from_filename(config_filename).ok(); // Loading environment file
+    Ok(config_filename)
+}
+
+// Loads environment variables from config file
+pub fn load_env() -> Result<()> {
+    dotenv::from_filename(get_config_filename()?).ok(); // Loading environment file
     Ok(())
 }
 
-// Obtain location of database file
-pub fn get_location_database() -> Result<PathBuf> {
+// Settings read from the config file only, the environment may define the same names for other programs
+// The deprecated iterator is the only way to parse the file without setting the variables
+#[allow(deprecated)]
+fn read_config_file() -> Vec<(String, String)> {
+    get_config_filename()
+        .ok()
+        .and_then(|filename| dotenv::from_path_iter(filename).ok())
+        .map(|entries| entries.filter_map(|entry| entry.ok()).collect())
+        .unwrap_or_default()
+}
+
+// Value of a setting of the config file
+pub fn get_config_setting(name: &str) -> Option<String> {
+    read_config_file()
+        .into_iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value)
+}
+
+// Settings of the config file named <prefix><NAME>, as (NAME, value)
+// Unknown names are reported and ignored
+pub fn get_config_settings(prefix: &str, names: &[&str]) -> Vec<(String, String)> {
+    get_all_config_settings(prefix)
+        .into_iter()
+        .filter(|(name, _)| {
+            let known = names.contains(&name.as_str());
+            if !known {
+                eprintln!(
+                    "Unknown setting {}{} in the configuration, ignored!",
+                    prefix, name
+                );
+            }
+            known
+        })
+        .collect()
+}
+
+// Settings of the config file named <prefix><NAME> whatever the name, as (NAME, value)
+pub fn get_all_config_settings(prefix: &str) -> Vec<(String, String)> {
+    read_config_file()
+        .into_iter()
+        .filter_map(|(key, value)| Some((key.strip_prefix(prefix)?.to_string(), value)))
+        .collect()
+}
+
+// Value of the setting NAME in the config file, or else in the preset, empty if neither defines it
+pub fn lookup_setting<'a>(
+    name: &str,
+    config: &'a [(String, String)],
+    preset: &[(&str, &'a str)],
+) -> &'a str {
+    config
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+        .or_else(|| {
+            preset
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| *value)
+        })
+        .unwrap_or("")
+}
+
+// Obtain location of data folder
+fn get_data_dir() -> Result<PathBuf> {
     let path_root = match ProjectDirs::from("", "", APPNAME) {
         Some(proj_dirs) => proj_dirs.data_dir().to_path_buf(),
         None => bail!("Cannot find configuration folder!"),
     };
     std::fs::create_dir_all(&path_root)?; // Creating the config directory if it does not exist!
-    Ok(path_root.join("rustdo_db.json"))
+    Ok(path_root)
+}
+
+// Obtain location of database file for a given list
+pub fn get_location_database(list: &str) -> Result<PathBuf> {
+    let path_root = get_data_dir()?;
+    match list {
+        DEFAULT_LIST => Ok(path_root.join(format!("{}.json", DB_PREFIX))),
+        _ => Ok(path_root.join(format!("{}_{}.json", DB_PREFIX, list))),
+    }
+}
+
+// Obtain location of the archive file for a given list
+pub fn get_location_archive(list: &str) -> Result<PathBuf> {
+    let path_root = get_data_dir()?;
+    match list {
+        DEFAULT_LIST => Ok(path_root.join(format!("{}.json", ARCHIVE_PREFIX))),
+        _ => Ok(path_root.join(format!("{}_{}.json", ARCHIVE_PREFIX, list))),
+    }
 }
 
-// DB connection function
-pub fn connect_db() -> Result<DatabaseModel> {
-    let filename = get_location_database()?;
+// Obtain location of the history journal
+pub fn get_location_history() -> Result<PathBuf> {
+    Ok(get_data_dir()?.join(HISTORY_FILENAME))
+}
+
+// Checks that a list name can be used in a file name
+pub fn validate_list_name(list: &str) -> Result<()> {
+    if list.is_empty()
+        || !list
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!(
+            "Invalid list name '{}' (use letters, digits, '-' and '_')!",
+            list
+        );
+    }
+    Ok(())
+}
+
+// Lists the names of all existing lists, the default list first
+pub fn get_lists() -> Result<Vec<String>> {
+    let prefix = format!("{}_", DB_PREFIX);
+    let mut lists: Vec<String> = std::fs::read_dir(get_data_dir()?)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter_map(|name| {
+            name.strip_prefix(&prefix)
+                .and_then(|name| name.strip_suffix(".json"))
+                .map(|list| list.to_string())
+        })
+        .collect();
+    lists.sort();
+    lists.insert(0, DEFAULT_LIST.to_string());
+    Ok(lists)
+}
+
+// DB connection function, entries written with an older schema are migrated first
+pub fn connect_db(list: &str) -> Result<DatabaseModel> {
+    validate_list_name(list)?;
+    let filename = get_location_database(list)?;
+    migrate_database(&filename)?;
     let db: DatabaseModel = Database::new(filename).with_context(|| "Error opening database!")?;
     Ok(db)
 }
 
+// Archive connection function, the archive stores TODOs like the DB of the list
+pub fn connect_archive(list: &str) -> Result<DatabaseModel> {
+    validate_list_name(list)?;
+    let filename = get_location_archive(list)?;
+    migrate_database(&filename)?;
+    let archive: DatabaseModel =
+        Database::new(filename).with_context(|| "Error opening archive!")?;
+    Ok(archive)
+}
+
 // Queries the DB for all TODOs
 pub fn get_todos(db: &mut DatabaseModel) -> Result<Vec<(String, Todo)>> {
     let query = db
@@ -55,15 +204,52 @@ pub fn get_todos(db: &mut DatabaseModel) -> Result<Vec<(String, Todo)>> {
     Ok(query)
 }
 
-// Queries the DB for all completed TODOs
-pub fn get_completed_todos(db: &mut DatabaseModel) -> Result<Vec<(String, Todo)>> {
-    let query: Vec<(String, Todo)> = db
-        .query(query!(completed == true))?
-        .filter_map(|x| x.ok())
+// Queries the DB for the TODOs matching a filter
+// The completion state is pushed down to a DB query, the other predicates are checked on each TODO
+pub fn get_filtered_todos(db: &mut DatabaseModel, filter: &Filter) -> Result<Vec<(String, Todo)>> {
+    let candidates = match filter.get_completed() {
+        Some(true) => db
+            .query(query!(completed == true))?
+            .filter_map(|x| x.ok())
+            .collect::<Vec<(String, Todo)>>(),
+        Some(false) => db
+            .query(query!(completed == false))?
+            .filter_map(|x| x.ok())
+            .collect::<Vec<(String, Todo)>>(),
+        None => get_todos(db)?,
+    };
+    let query = candidates
+        .into_iter()
+        .filter(|(_, todo)| filter.matches(todo))
         .collect::<Vec<(String, Todo)>>();
     Ok(query)
 }
 
+// Queries the DB for the TODO with the given id
+pub fn get_todo(db: &mut DatabaseModel, id: &str) -> Result<Todo> {
+    let todo = db
+        .get(id)
+        .with_context(|| format!("No TODO with id {}!", id))?;
+    Ok(todo)
+}
+
+// Queries the DB for the unique TODO whose id starts with the given prefix
+pub fn get_todo_by_id_prefix(db: &mut DatabaseModel, prefix: &str) -> Result<Todo> {
+    if prefix.is_empty() {
+        bail!("Empty id prefix!");
+    }
+    let mut matches = db
+        .iter()
+        .filter_map(|x| x.ok())
+        .filter(|(id, _)| id.starts_with(prefix))
+        .collect::<Vec<(String, Todo)>>();
+    match matches.len() {
+        0 => bail!("No TODO matches the id prefix '{}'!", prefix),
+        1 => Ok(matches.remove(0).1),
+        n => bail!("The id prefix '{}' is ambiguous ({} matches)!", prefix, n),
+    }
+}
+
 // Inserts a TODO object inside the DB
 pub fn insert_todo(db: &mut DatabaseModel, todo: &Todo) -> Result<()> {
     db.set(todo.get_id(), todo)
@@ -80,7 +266,11 @@ pub fn update_todo(db: &mut DatabaseModel, todo_replace: &Todo) -> Result<()> {
 
 // Deletes a TODO object from DB
 pub fn delete_todo(db: &mut DatabaseModel, todo: &Todo) -> Result<()> {
-    db.delete(todo.get_id())
-        .with_context(|| "Error updating the entry!")?;
+    delete_todo_by_id(db, todo.get_id())
+}
+
+// Deletes the DB element with the given id
+pub fn delete_todo_by_id(db: &mut DatabaseModel, id: &str) -> Result<()> {
+    db.delete(id).with_context(|| "Error updating the entry!")?;
     Ok(())
 }
@@ -0,0 +1,434 @@
+use crate::{
+    model::{DueStatus, MAXPRIORITY},
+    storage::{get_config_setting, get_config_settings, lookup_setting},
+};
+use anyhow::{bail, Context, Result};
+use console::Style;
+use once_cell::sync::OnceCell;
+use std::env;
+
+// Style settings override the THEME preset element by element, e.g. COLOR_OVERDUE="bold.red.on_white"
+// A style is a dotted list of colors and attributes, e.g. "bold.red.on_white" or "reverse"
+const COLOR_PREFIX: &str = "COLOR_";
+// Optional parts of the template are written between brackets, they are left out when a placeholder is empty
+const DEFAULT_TEMPLATE: &str =
+    "{priority} {title}[ {tags}][ {notes}][ {start}][ - Due: {due}] - Progress: {progress}";
+
+// Elements of the list which can be styled, the priority levels are PRIORITY_0 to PRIORITY_<MAXPRIORITY>
+const ELEMENTS: [&str; 7] = [
+    "OVERDUE",
+    "TODAY",
+    "FUTURE",
+    "COMPLETED",
+    "COMPLETED_TITLE",
+    "TAGS",
+    "SELECTION",
+];
+const ATTRIBUTES: [&str; 9] = [
+    "bright",
+    "bold",
+    "dim",
+    "underlined",
+    "blink",
+    "blink_fast",
+    "reverse",
+    "hidden",
+    "strikethrough",
+];
+const COLORS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+// Default styles, in the same format as the configuration
+const DEFAULT_PRESET: [(&str, &str); 7] = [
+    ("OVERDUE", "red"),
+    ("TODAY", "yellow"),
+    ("FUTURE", "green"),
+    ("COMPLETED", "dim"),
+    ("COMPLETED_TITLE", "strikethrough"),
+    ("TAGS", "cyan"),
+    ("SELECTION", ""),
+];
+
+// Bold colors on backgrounds for the due dates, the selected row is reversed
+const HIGH_CONTRAST_PRESET: [(&str, &str); 10] = [
+    ("OVERDUE", "bold.white.on_red"),
+    ("TODAY", "bold.black.on_yellow"),
+    ("FUTURE", "bold.green"),
+    ("COMPLETED", "dim"),
+    ("COMPLETED_TITLE", "strikethrough.dim"),
+    ("TAGS", "bold.cyan"),
+    ("SELECTION", "reverse.bold"),
+    ("PRIORITY_1", "bold"),
+    ("PRIORITY_2", "bold.yellow"),
+    ("PRIORITY_3", "bold.red"),
+];
+
+// Attributes only, for terminals without colors
+const MONO_PRESET: [(&str, &str); 9] = [
+    ("OVERDUE", "bold.underlined"),
+    ("TODAY", "bold"),
+    ("FUTURE", ""),
+    ("COMPLETED", "dim"),
+    ("COMPLETED_TITLE", "strikethrough"),
+    ("TAGS", ""),
+    ("SELECTION", "reverse"),
+    ("PRIORITY_2", "bold"),
+    ("PRIORITY_3", "bold"),
+];
+
+// Placeholders of the row template
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RowField {
+    Priority,
+    Title,
+    Tags,
+    Notes,
+    Start,
+    Due,
+    Progress,
+}
+impl RowField {
+    fn parse(name: &str) -> Result<RowField> {
+        match name {
+            "priority" => Ok(RowField::Priority),
+            "title" => Ok(RowField::Title),
+            "tags" => Ok(RowField::Tags),
+            "notes" => Ok(RowField::Notes),
+            "start" => Ok(RowField::Start),
+            "due" => Ok(RowField::Due),
+            "progress" => Ok(RowField::Progress),
+            _ => bail!("Unknown placeholder {{{}}}!", name),
+        }
+    }
+}
+
+// Part of the row template, optional parts contain text and placeholders
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Text(String),
+    Field(RowField),
+    Optional(Vec<Segment>),
+}
+
+// Styles of the list and template of its rows
+pub struct Theme {
+    overdue: Style,
+    today: Style,
+    future: Style,
+    completed: Style,
+    completed_title: Style,
+    tags: Style,
+    selection: Option<Style>,
+    priorities: Vec<Style>,
+    template: Vec<Segment>,
+}
+impl Theme {
+    // Reads the preset selected with THEME="default"|"high-contrast"|"mono", the COLOR_<ELEMENT> styles
+    // and the ROW_TEMPLATE of the config file
+    // Unknown styles and placeholders are errors, unknown elements are only reported
+    pub fn load() -> Result<Theme> {
+        let preset: &[(&str, &str)] = match get_config_setting("THEME")
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "" | "default" => &DEFAULT_PRESET,
+            "high-contrast" => &HIGH_CONTRAST_PRESET,
+            "mono" => &MONO_PRESET,
+            other => bail!(
+                "Unknown theme '{}', expected 'default', 'high-contrast' or 'mono'!",
+                other
+            ),
+        };
+        let priority_names = get_priority_names();
+        let names: Vec<&str> = ELEMENTS
+            .iter()
+            .copied()
+            .chain(priority_names.iter().map(|name| name.as_str()))
+            .collect();
+        let config = get_config_settings(COLOR_PREFIX, &names);
+        let template = get_config_setting("ROW_TEMPLATE").unwrap_or(DEFAULT_TEMPLATE.to_string());
+        Theme::build(preset, &config, &template)
+    }
+    fn build(
+        preset: &[(&str, &str)],
+        config: &[(String, String)],
+        template: &str,
+    ) -> Result<Theme> {
+        let get_style = |name: &str| -> Result<Style> {
+            parse_style(lookup_setting(name, config, preset))
+                .with_context(|| format!("Invalid style {}{}!", COLOR_PREFIX, name))
+        };
+        let selection = match get_style("SELECTION")? {
+            style if style == Style::new() => None,
+            style => Some(style),
+        };
+        Ok(Theme {
+            overdue: get_style("OVERDUE")?,
+            today: get_style("TODAY")?,
+            future: get_style("FUTURE")?,
+            completed: get_style("COMPLETED")?,
+            completed_title: get_style("COMPLETED_TITLE")?,
+            tags: get_style("TAGS")?,
+            selection,
+            priorities: get_priority_names()
+                .iter()
+                .map(|name| get_style(name))
+                .collect::<Result<Vec<Style>>>()?,
+            template: parse_template(template).with_context(|| "Invalid ROW_TEMPLATE!")?,
+        })
+    }
+    // Style of a due date, the due date of a completed TODO is not highlighted
+    pub fn get_due_style(&self, status: Option<DueStatus>, completed: bool) -> &Style {
+        match (completed, status) {
+            (true, _) => &self.completed,
+            (false, Some(DueStatus::Overdue)) => &self.overdue,
+            (false, Some(DueStatus::Soon)) => &self.today,
+            (false, _) => &self.future,
+        }
+    }
+    pub fn get_completed_title_style(&self) -> &Style {
+        &self.completed_title
+    }
+    pub fn get_tags_style(&self) -> &Style {
+        &self.tags
+    }
+    pub fn get_priority_style(&self, priority: u32) -> &Style {
+        &self.priorities[(priority as usize).min(self.priorities.len() - 1)]
+    }
+    // Style of the selected row, None when only the marker shows the selection
+    pub fn get_selection_style(&self) -> Option<&Style> {
+        self.selection.as_ref()
+    }
+    // Fills the row template with the formatted fields, empty fields leave out the optional parts
+    pub fn render_row(&self, get_field: impl Fn(RowField) -> String) -> String {
+        render_segments(&self.template, &get_field)
+    }
+}
+
+static THEME: OnceCell<Theme> = OnceCell::new();
+
+// Sets the active theme, read from the configuration on startup
+pub fn set_theme(theme: Theme) {
+    THEME.set(theme).ok();
+}
+
+// Active theme, the default theme until one is set
+pub fn get_theme() -> &'static Theme {
+    THEME.get_or_init(|| Theme::build(&DEFAULT_PRESET, &[], DEFAULT_TEMPLATE).unwrap())
+}
+
+// Names of the styles of the priority levels, PRIORITY_0 to PRIORITY_<MAXPRIORITY>
+fn get_priority_names() -> Vec<String> {
+    (0..=MAXPRIORITY)
+        .map(|level| format!("PRIORITY_{}", level))
+        .collect()
+}
+
+// NO_COLOR (https://no-color.org) disables all the styles, including the attributes of the theme
+pub fn apply_no_color() {
+    if env::var("NO_COLOR").is_ok_and(|value| !value.is_empty()) {
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    }
+}
+
+// Parses a dotted style, unlike Style::from_dotted_str unknown parts are errors
+fn parse_style(definition: &str) -> Result<Style> {
+    for part in definition.split('.').filter(|part| !part.is_empty()) {
+        let color = part.strip_prefix("on_").unwrap_or(part);
+        if !ATTRIBUTES.contains(&part)
+            && !COLORS.contains(&color)
+            && color != "bright"
+            && color.parse::<u8>().is_err()
+        {
+            bail!("Unknown color or attribute '{}'!", part);
+        }
+    }
+    Ok(Style::from_dotted_str(definition))
+}
+
+// Parses a row template, e.g. "{priority} {title}[ - Due: {due}]"
+fn parse_template(template: &str) -> Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut optional: Option<Vec<Segment>> = None;
+    let mut chars = template.chars();
+    let mut text = String::new();
+    while let Some(c) = chars.next() {
+        let current = optional.as_mut().unwrap_or(&mut segments);
+        match c {
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => bail!("Unclosed placeholder {{{}!", name),
+                    }
+                }
+                if !text.is_empty() {
+                    current.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                current.push(Segment::Field(RowField::parse(&name)?));
+            }
+            '[' if optional.is_none() => {
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                optional = Some(Vec::new());
+            }
+            ']' => match optional.take() {
+                Some(mut parts) => {
+                    if !text.is_empty() {
+                        parts.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::Optional(parts));
+                }
+                None => bail!("Unmatched ']'!"),
+            },
+            '[' => bail!("Optional parts cannot be nested!"),
+            c => text.push(c),
+        }
+    }
+    if optional.is_some() {
+        bail!("Unmatched '['!");
+    }
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    Ok(segments)
+}
+
+fn render_segments(segments: &[Segment], get_field: &impl Fn(RowField) -> String) -> String {
+    segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => text.clone(),
+            Segment::Field(field) => get_field(*field),
+            // Each field is formatted once, the part is left out if one of them is empty
+            Segment::Optional(parts) => {
+                let rendered: Vec<(bool, String)> = parts
+                    .iter()
+                    .map(|part| match part {
+                        Segment::Field(field) => {
+                            let value = get_field(*field);
+                            (value.is_empty(), value)
+                        }
+                        part => (
+                            false,
+                            render_segments(std::slice::from_ref(part), get_field),
+                        ),
+                    })
+                    .collect();
+                match rendered.iter().any(|(is_empty, _)| *is_empty) {
+                    true => String::new(),
+                    false => rendered.into_iter().map(|(_, value)| value).collect(),
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> Segment {
+        Segment::Text(text.to_string())
+    }
+
+    fn render(template: &str, tags: &str, due: &str) -> String {
+        let segments = parse_template(template).unwrap();
+        render_segments(&segments, &|field| match field {
+            RowField::Priority => "!!".to_string(),
+            RowField::Title => "Write report".to_string(),
+            RowField::Tags => tags.to_string(),
+            RowField::Due => due.to_string(),
+            _ => String::new(),
+        })
+    }
+
+    #[test]
+    fn parses_templates() {
+        assert_eq!(
+            parse_template("{priority} {title}[ - Due: {due}]!").unwrap(),
+            vec![
+                Segment::Field(RowField::Priority),
+                text(" "),
+                Segment::Field(RowField::Title),
+                Segment::Optional(vec![text(" - Due: "), Segment::Field(RowField::Due)]),
+                text("!"),
+            ]
+        );
+        assert!(parse_template(DEFAULT_TEMPLATE).is_ok());
+        assert!(parse_template("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn reports_template_errors() {
+        assert!(parse_template("{title} {prority}").is_err());
+        assert!(parse_template("{title").is_err());
+        assert!(parse_template("{title}[ {due}").is_err());
+        assert!(parse_template("{title}]").is_err());
+        assert!(parse_template("{title}[ [{due}]]").is_err());
+    }
+
+    #[test]
+    fn leaves_out_empty_optional_parts() {
+        let template = "{priority} {title}[ {tags}][ - Due: {due} ({tags})]";
+        assert_eq!(
+            render(template, "#work", "25-10"),
+            "!! Write report #work - Due: 25-10 (#work)"
+        );
+        assert_eq!(render(template, "", "25-10"), "!! Write report");
+        assert_eq!(render(template, "#work", ""), "!! Write report #work");
+        // Empty fields outside of brackets are kept as they are
+        assert_eq!(render("{title}|{notes}|", "", ""), "Write report||");
+    }
+
+    #[test]
+    fn parses_styles() {
+        for definition in [
+            "bold.red.on_white",
+            "reverse",
+            "",
+            "196.on_21",
+            "bright.blue",
+        ] {
+            assert_eq!(
+                parse_style(definition).unwrap(),
+                Style::from_dotted_str(definition)
+            );
+        }
+        assert!(parse_style("bold.purple").is_err());
+        assert!(parse_style("on_purple").is_err());
+        assert!(parse_style("256").is_err());
+        assert!(parse_style("blink.").is_ok());
+    }
+
+    #[test]
+    fn builds_presets() {
+        for preset in [&DEFAULT_PRESET[..], &HIGH_CONTRAST_PRESET, &MONO_PRESET] {
+            assert!(Theme::build(preset, &[], DEFAULT_TEMPLATE).is_ok());
+        }
+        let config = vec![("TAGS".to_string(), "bold.purple".to_string())];
+        assert!(Theme::build(&DEFAULT_PRESET, &config, DEFAULT_TEMPLATE).is_err());
+        assert!(Theme::build(&DEFAULT_PRESET, &[], "{title} {owner}").is_err());
+    }
+
+    #[test]
+    fn disables_styles_with_no_color() {
+        env::set_var("NO_COLOR", "1");
+        apply_no_color();
+        assert!(!console::colors_enabled());
+        let theme = Theme::build(&HIGH_CONTRAST_PRESET, &[], DEFAULT_TEMPLATE).unwrap();
+        let tags = theme.get_tags_style().apply_to("#work").to_string();
+        assert_eq!(tags, "#work");
+        let row = theme.render_row(|field| match field {
+            RowField::Tags => tags.clone(),
+            _ => String::new(),
+        });
+        assert!(!row.contains('\x1b'));
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{filter::Filter, model::SortingMethod, storage::get_all_config_settings};
+use anyhow::{bail, Context, Result};
+
+// Views are defined in the config file as VIEW_<NAME>="<filter> | <sort>", either part may be omitted
+const VIEW_PREFIX: &str = "VIEW_";
+
+// Named combination of a filter and a sorting method
+#[derive(Clone)]
+pub struct View {
+    name: String,
+    filter: Option<Filter>,
+    sort: Option<SortingMethod>,
+}
+impl View {
+    // Parses the value of a VIEW_<NAME> setting
+    pub fn parse(name: &str, definition: &str) -> Result<View> {
+        let (filter_str, sort_str) = definition.split_once('|').unwrap_or((definition, ""));
+        let filter = match filter_str.trim() {
+            "" => None,
+            expression => Some(Filter::parse(expression)?),
+        };
+        let sort = match sort_str.trim() {
+            "" => None,
+            sort_str => Some(SortingMethod::parse(sort_str)?),
+        };
+        Ok(View {
+            name: normalize_view_name(name),
+            filter,
+            sort,
+        })
+    }
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    pub fn get_filter(&self) -> &Option<Filter> {
+        &self.filter
+    }
+    pub fn get_sort(&self) -> &Option<SortingMethod> {
+        &self.sort
+    }
+}
+
+// View names are case-insensitive, '_' in setting names is written '-'
+fn normalize_view_name(name: &str) -> String {
+    name.to_lowercase().replace('_', "-")
+}
+
+// Reads all the views defined in the config file, sorted by name
+// Like the key bindings, the views are not read from the environment
+pub fn get_views() -> Result<Vec<View>> {
+    let mut views = get_all_config_settings(VIEW_PREFIX)
+        .into_iter()
+        .filter(|(name, _)| !name.is_empty())
+        .map(|(name, value)| {
+            View::parse(&name, &value)
+                .with_context(|| format!("Invalid view {}{}!", VIEW_PREFIX, name))
+        })
+        .collect::<Result<Vec<View>>>()?;
+    views.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(views)
+}
+
+// Reads the view with the given name from the configuration
+pub fn get_view(name: &str) -> Result<View> {
+    let name = normalize_view_name(name);
+    match get_views()?.into_iter().find(|view| view.name == name) {
+        Some(view) => Ok(view),
+        None => bail!("No view named '{}' in the configuration!", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_view_names() {
+        assert_eq!(normalize_view_name("TODAY"), "today");
+        assert_eq!(normalize_view_name("WORK_HIGH"), "work-high");
+        assert_eq!(normalize_view_name("work-high"), "work-high");
+    }
+
+    #[test]
+    fn parses_views() {
+        let view = View::parse("WORK_HIGH", "tag:work priority>=2 | priority, due").unwrap();
+        assert_eq!(view.get_name(), "work-high");
+        assert_eq!(
+            view.get_filter(),
+            &Some(Filter::parse("tag:work priority>=2").unwrap())
+        );
+        assert_eq!(
+            view.get_sort().as_ref().map(|sort| sort.to_string()),
+            Some(SortingMethod::parse("priority, due").unwrap().to_string())
+        );
+        let view = View::parse("TODAY", "due<=today !done").unwrap();
+        assert!(view.get_filter().is_some());
+        assert!(view.get_sort().is_none());
+        let view = View::parse("BY_TITLE", " | title").unwrap();
+        assert!(view.get_filter().is_none());
+        assert!(view.get_sort().is_some());
+        let view = View::parse("ALL", "").unwrap();
+        assert!(view.get_filter().is_none() && view.get_sort().is_none());
+    }
+
+    #[test]
+    fn reports_invalid_views() {
+        assert!(View::parse("BAD", "prority>=2").is_err());
+        assert!(View::parse("BAD", "tag:work | nonsense").is_err());
+    }
+}